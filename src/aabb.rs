@@ -13,6 +13,18 @@ impl Default for Aabb {
 }
 
 impl Aabb{
+	#[inline]
+	pub const fn new(min:Planar64Vec3,max:Planar64Vec3)->Self{
+		Self{min,max}
+	}
+	#[inline]
+	pub fn min(&self)->Planar64Vec3{
+		self.min
+	}
+	#[inline]
+	pub fn max(&self)->Planar64Vec3{
+		self.max
+	}
 	pub fn grow(&mut self,point:Planar64Vec3){
 		self.min=self.min.min(point);
 		self.max=self.max.max(point);
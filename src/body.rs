@@ -0,0 +1,88 @@
+use crate::aabb::Aabb;
+use crate::integer::{Time,Planar64,Planar64Vec3};
+use crate::polynomial::Polynomial;
+
+//kinematic state at a point in time
+#[derive(Clone,Copy,Default,Hash,Eq,PartialEq)]
+pub struct Body{
+	pub position:Planar64Vec3,//I64 where 2^32 = 1 u
+	pub velocity:Planar64Vec3,//I64 where 2^32 = 1 u/s
+	pub acceleration:Planar64Vec3,//I64 where 2^32 = 1 u/s/s
+	pub time:Time,//nanoseconds x xxx xxx xxx
+}
+impl std::fmt::Display for Body{
+	fn fmt(&self,f:&mut std::fmt::Formatter<'_>)->std::fmt::Result{
+		write!(f,"p({}) v({}) a({}) t({})",self.position,self.velocity,self.acceleration,self.time)
+	}
+}
+impl Body{
+	#[inline]
+	pub const fn new(position:Planar64Vec3,velocity:Planar64Vec3,acceleration:Planar64Vec3,time:Time)->Self{
+		Self{
+			position,
+			velocity,
+			acceleration,
+			time,
+		}
+	}
+	pub fn extrapolated_position(&self,time:Time)->Planar64Vec3{
+		let dt=time-self.time;
+		self.position+self.velocity*dt+self.acceleration*(dt*dt/2)
+	}
+	pub fn extrapolated_velocity(&self,time:Time)->Planar64Vec3{
+		let dt=time-self.time;
+		self.velocity+self.acceleration*dt
+	}
+	pub fn extrapolate(&self,time:Time)->Self{
+		Self{
+			position:self.extrapolated_position(time),
+			velocity:self.extrapolated_velocity(time),
+			acceleration:self.acceleration,
+			time,
+		}
+	}
+	//position as a function of time since self.time
+	#[inline]
+	pub fn position_polynomial(&self)->Polynomial<Planar64Vec3,3>{
+		Polynomial::kinematic(self.position,self.velocity,self.acceleration)
+	}
+	//bounding box of the path travelled over [t0,t1]
+	pub fn swept_aabb(&self,t0:Time,t1:Time)->Aabb{
+		let mut aabb=Aabb::default();
+		aabb.grow(self.extrapolated_position(t0));
+		aabb.grow(self.extrapolated_position(t1));
+		//the path can only bulge past the endpoints where a velocity component changes sign
+		let polynomial=self.position_polynomial();
+		for axis in [Planar64Vec3::X,Planar64Vec3::Y,Planar64Vec3::Z]{
+			for dt in polynomial.dot(axis).derivative().zeroes_in(t0-self.time,t1-self.time){
+				aabb.grow(self.extrapolated_position(self.time+dt));
+			}
+		}
+		aabb
+	}
+	//first time in [t0,t1) when the position crosses the plane dot(normal,p)==offset
+	pub fn time_to_plane(&self,normal:Planar64Vec3,offset:Planar64,t0:Time,t1:Time)->Option<Time>{
+		let distance=self.position_polynomial().dot(normal)-Polynomial::new([offset,Planar64::ZERO,Planar64::ZERO]);
+		distance.zeroes_in(t0-self.time,t1-self.time).first().map(|&dt|self.time+dt)
+	}
+	//time when the velocity along up stops rising, None if it is not rising or never stops
+	pub fn time_to_apex(&self,up:Planar64Vec3)->Option<Time>{
+		let up_velocity=self.velocity.dot128(up);
+		let up_acceleration=self.acceleration.dot128(up);
+		if up_velocity<=0||0<=up_acceleration{
+			return None;
+		}
+		Some(self.time+Time::from_nanos((-up_velocity*1_000_000_000/up_acceleration) as i64))
+	}
+}
+
+#[test]
+fn test_time_to_apex(){
+	let gravity=Planar64Vec3::int(0,-10,0);
+	let rising=Body::new(Planar64Vec3::ZERO,Planar64Vec3::int(0,15,0),gravity,Time::from_secs(5));
+	assert_eq!(rising.time_to_apex(Planar64Vec3::Y),Some(Time::from_millis(6500)));
+	let falling=Body::new(Planar64Vec3::ZERO,Planar64Vec3::int(0,-10,0),gravity,Time::from_secs(5));
+	assert_eq!(falling.time_to_apex(Planar64Vec3::Y),None);
+	let floating=Body::new(Planar64Vec3::ZERO,Planar64Vec3::int(0,10,0),Planar64Vec3::ZERO,Time::ZERO);
+	assert_eq!(floating.time_to_apex(Planar64Vec3::Y),None);
+}
@@ -1,4 +1,10 @@
-use crate::integer::{Time,Planar64,Planar64Vec3};
+use std::collections::HashMap;
+use crate::aabb::Aabb;
+use crate::body::Body;
+use crate::gameplay_style::{StyleModifiers,StyleModifiersOverride};
+use crate::model::{Model,ModelId};
+use crate::gameplay_modes::{Mode,ModeId,StageId,StageElement,StageElementBehaviour};
+use crate::integer::{Time,Angle32,Planar64,Planar64Vec3,Planar64Mat3,Planar64Affine3,isqrt_i128};
 
 //you have this effect while in contact
 #[derive(Clone,Hash,Eq,PartialEq)]
@@ -12,6 +18,56 @@ pub enum ContactingBehaviour{
 	Ladder(ContactingLadder),
 	Elastic(u32),//[1/2^32,1] 0=None (elasticity+1)/2^32
 }
+impl ContactingBehaviour{
+	//velocity after contacting a surface, normal points out of the surface
+	pub fn contact_velocity(&self,velocity:Planar64Vec3,normal:Planar64Vec3,style:&StyleModifiers)->Planar64Vec3{
+		match self{
+			//surfing is a regular slide, the surf_slope decides that you can't walk on it
+			ContactingBehaviour::Surf=>clip_velocity(velocity,normal),
+			//stuck to the surface in both directions
+			ContactingBehaviour::Cling=>reject(velocity,normal),
+			ContactingBehaviour::Ladder(ladder)=>{
+				//pushing into the ladder steeply enough turns into climbing it
+				let into_ladder=-velocity.dot(normal)/normal.length();
+				let speed=velocity.length();
+				let climbing=Planar64::ZERO<speed&&style.ladder_dot()<into_ladder/speed;
+				let mut velocity=if ladder.sticky{
+					reject(velocity,normal)
+				}else{
+					clip_velocity(velocity,normal)
+				};
+				if climbing{
+					let up=reject(-style.gravity(),normal);
+					if up!=Planar64Vec3::ZERO{
+						velocity+=up.with_length(into_ladder);
+					}
+				}
+				let speed=velocity.length();
+				if style.ladder_speed()<speed{
+					velocity=velocity.with_length(style.ladder_speed());
+				}
+				velocity
+			},
+			&ContactingBehaviour::Elastic(elasticity)=>{
+				let vn=velocity.dot128(normal);
+				if 0<=vn{
+					return velocity;
+				}
+				//reflect the normal component scaled by the restitution (elasticity+1)/2^32
+				let restitution=elasticity as i128+1;
+				velocity-scale_div(normal,(vn*((1<<32)+restitution))>>32,normal.dot128(normal))
+			},
+		}
+	}
+}
+//remove the part of the velocity going into the surface
+pub fn clip_velocity(velocity:Planar64Vec3,normal:Planar64Vec3)->Planar64Vec3{
+	let vn=velocity.dot128(normal);
+	if 0<=vn{
+		return velocity;
+	}
+	velocity-scale_div(normal,vn,normal.dot128(normal))
+}
 //you have this effect while intersecting
 #[derive(Clone,Hash,Eq,PartialEq)]
 pub struct IntersectingWater{
@@ -19,6 +75,44 @@ pub struct IntersectingWater{
 	pub density:Planar64,
 	pub velocity:Planar64Vec3,
 }
+impl IntersectingWater{
+	//archimedes: the weight of the displaced water pushes up
+	pub fn buoyancy(&self,submerged_volume:Planar64,style:&StyleModifiers)->Planar64Vec3{
+		-style.gravity()*(self.density*submerged_volume/style.mass())
+	}
+	//linear drag towards the water current
+	pub fn drag(&self,velocity:Planar64Vec3,style:&StyleModifiers)->Planar64Vec3{
+		(self.velocity-velocity)*(self.viscosity/style.mass())
+	}
+	//acceleration from the water on top of gravity for a body at position inside this water volume
+	pub fn acceleration(&self,position:Planar64Vec3,velocity:Planar64Vec3,water:&Aabb,style:&StyleModifiers)->Planar64Vec3{
+		let submerged_volume=style.hitbox().submerged_volume(position,water);
+		self.buoyancy(submerged_volume,style)+self.drag(velocity,style)
+	}
+	//velocity a swimmer aims for, carried along by the current
+	pub fn swim_velocity(&self,control_dir:Planar64Vec3,style:&StyleModifiers)->Planar64Vec3{
+		if control_dir==Planar64Vec3::ZERO{
+			self.velocity
+		}else{
+			self.velocity+control_dir.with_length(style.swim_speed())
+		}
+	}
+}
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub enum GravityBehaviour{
+	Replace(Planar64Vec3),//anti-gravity tunnels, low gravity rooms
+	Add(Planar64Vec3),//wind
+}
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub struct IntersectingGravity{
+	pub behaviour:GravityBehaviour,
+	pub priority:u32,
+}
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub struct IntersectingStyle{
+	pub modifiers:StyleModifiersOverride,
+	pub priority:u32,
+}
 //All models can be given these attributes
 #[derive(Clone,Hash,Eq,PartialEq)]
 pub struct Accelerator{
@@ -26,9 +120,33 @@ pub struct Accelerator{
 }
 #[derive(Clone,Hash,Eq,PartialEq)]
 pub enum Booster{
-	//Affine(crate::integer::Planar64Affine3),//capable of SetVelocity,DotVelocity,normal booster,bouncy part,redirect velocity, and much more
+	Affine(Planar64Affine3),//capable of SetVelocity,DotVelocity,normal booster,bouncy part,redirect velocity, and much more
 	Velocity(Planar64Vec3),//straight up boost velocity adds to your current velocity
-	Energy{direction:Planar64Vec3,energy:Planar64},//increase energy in direction
+	//increase energy in direction.
+	//moving against the direction the energy first slows you down, you only reverse once it is used up.
+	//negative energy pushes the opposite way in the same manner.
+	Energy{direction:Planar64Vec3,energy:Planar64},
+}
+impl Booster{
+	pub fn boost(&self,velocity:Planar64Vec3,mass:Planar64)->Planar64Vec3{
+		match self{
+			Booster::Affine(affine)=>affine.transform_point3(velocity),
+			&Booster::Velocity(boost_velocity)=>velocity+boost_velocity,
+			&Booster::Energy{direction,energy}=>{
+				if direction==Planar64Vec3::ZERO||mass<=Planar64::ZERO{
+					return velocity;
+				}
+				let direction=direction.with_length(Planar64::ONE);
+				//1/2*m*v'^2=1/2*m*v^2+E using energy signed by the direction of travel
+				//so the speed passes through zero instead of flipping.
+				//v'|v'|=v|v|+2*E/m in units of 2^64
+				let speed=velocity.dot(direction);
+				let signed_square=speed.get() as i128*(speed.get() as i128).abs()+((energy.get() as i128*2)<<64)/mass.get() as i128;
+				let boosted_speed=Planar64::raw((signed_square.signum()*isqrt_i128(signed_square.abs())) as i64);
+				velocity+direction*(boosted_speed-speed)
+			},
+		}
+	}
 }
 #[derive(Clone,Hash,Eq,PartialEq)]
 pub enum TrajectoryChoice{
@@ -64,24 +182,254 @@ impl SetTrajectory{
 			|SetTrajectory::Velocity(_)=>true,
 		}
 	}
+	//the velocity you have after the trajectory is set.
+	//every variant is defined kinematically, so mass does not enter into it.
+	pub fn velocity(&self,position:Planar64Vec3,velocity:Planar64Vec3,gravity:Planar64Vec3)->Planar64Vec3{
+		match *self{
+			SetTrajectory::AirTime(air_time)=>{
+				//rise for half the air time
+				reject(velocity,gravity)-gravity*air_time/2
+			},
+			SetTrajectory::Height(height)=>{
+				//a height at or below zero needs no upward speed
+				if height<=Planar64::ZERO||gravity==Planar64Vec3::ZERO{
+					return reject(velocity,gravity);
+				}
+				//v^2=2*g*h
+				let up_speed=(gravity.length()*height*2).sqrt();
+				reject(velocity,gravity)+(-gravity).with_length(up_speed)
+			},
+			SetTrajectory::DotVelocity{direction,dot}=>{
+				reject(velocity,direction)+scale_div(direction,(dot.get() as i128)<<32,direction.dot128(direction))
+			},
+			SetTrajectory::TargetPointTime{target_point,time}=>{
+				if time==Time::ZERO{
+					return velocity;
+				}
+				//p+v*t+g*t^2/2=target
+				scale_div(target_point-position,1_000_000_000,time.nanos() as i128)-gravity*time/2
+			},
+			SetTrajectory::TargetPointSpeed{target_point,speed,ref trajectory_choice}=>{
+				target_point_speed(target_point-position,speed,gravity,trajectory_choice).unwrap_or(velocity)
+			},
+			SetTrajectory::Velocity(velocity)=>velocity,
+		}
+	}
+}
+//v*num/den per axis without intermediate rounding
+fn scale_div(v:Planar64Vec3,num:i128,den:i128)->Planar64Vec3{
+	if den==0{
+		return Planar64Vec3::ZERO;
+	}
+	Planar64Vec3::raw(
+		(v.x().get() as i128*num/den) as i64,
+		(v.y().get() as i128*num/den) as i64,
+		(v.z().get() as i128*num/den) as i64,
+	)
+}
+//remove the component of v along direction
+fn reject(v:Planar64Vec3,direction:Planar64Vec3)->Planar64Vec3{
+	v-scale_div(direction,v.dot128(direction),direction.dot128(direction))
+}
+fn target_point_speed(displacement:Planar64Vec3,speed:Planar64,gravity:Planar64Vec3,trajectory_choice:&TrajectoryChoice)->Option<Planar64Vec3>{
+	if gravity==Planar64Vec3::ZERO{
+		if displacement==Planar64Vec3::ZERO{
+			return None;
+		}
+		return Some(displacement.with_length(speed));
+	}
+	//|d-g*t^2/2|^2=(s*t)^2 is a quadratic in u=t^2:
+	//g.g/4*u^2-(d.g+s^2)*u+d.d=0
+	let a2=gravity.dot(gravity)/4;
+	let a1=-(displacement.dot(gravity)+speed*speed);
+	let a0=displacement.dot(displacement);
+	let roots=crate::zeroes::zeroes2(a0,a1,a2);
+	let mut positive_roots=roots.iter().copied().filter(|&u|Planar64::ZERO<u);
+	let (u,exact)=match (positive_roots.next(),positive_roots.next(),trajectory_choice){
+		(Some(u),None,_)
+		|(Some(u),Some(_),TrajectoryChoice::LowArcShortDuration)
+		|(Some(_),Some(u),TrajectoryChoice::HighArcLongDuration)=>(u,true),
+		//speed is too low to reach the target, so take the trajectory which minimizes the miss
+		_=>(-a1/(a2*2),false),
+	};
+	if u<=Planar64::ZERO{
+		//far too slow, launch at the angle of maximum reach towards the target,
+		//which bisects the direction of the target and straight up
+		if displacement==Planar64Vec3::ZERO{
+			return None;
+		}
+		let towards=displacement.with_length(Planar64::ONE);
+		let direction=towards+(-gravity).with_length(Planar64::ONE);
+		let direction=if direction==Planar64Vec3::ZERO{towards}else{direction};
+		return Some(direction.with_length(speed));
+	}
+	let time=Time::from(u.sqrt());
+	if time==Time::ZERO{
+		return None;
+	}
+	let velocity=scale_div(displacement,1_000_000_000,time.nanos() as i128)-gravity*time/2;
+	if exact||velocity==Planar64Vec3::ZERO{
+		Some(velocity)
+	}else{
+		Some(velocity.with_length(speed))
+	}
+}
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub enum TrapCondition{
+	FasterThan(Planar64),
+	SlowerThan(Planar64),
+	InRange(Planar64,Planar64),//inclusive
+	OutsideRange(Planar64,Planar64),
+}
+impl TrapCondition{
+	//cmp(threshold) compares the measured value against a threshold
+	fn evaluate_by<F:Fn(Planar64)->std::cmp::Ordering>(&self,cmp:F)->bool{
+		use std::cmp::Ordering;
+		match *self{
+			TrapCondition::FasterThan(threshold)=>cmp(threshold)==Ordering::Greater,
+			TrapCondition::SlowerThan(threshold)=>cmp(threshold)==Ordering::Less,
+			TrapCondition::InRange(low,high)=>cmp(low)!=Ordering::Less&&cmp(high)!=Ordering::Greater,
+			TrapCondition::OutsideRange(low,high)=>cmp(low)==Ordering::Less||cmp(high)==Ordering::Greater,
+		}
+	}
+	pub fn evaluate(&self,value:Planar64)->bool{
+		self.evaluate_by(|threshold|value.cmp(&threshold))
+	}
+}
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub enum TrapMeasure{
+	Speed,
+	Velocity(Planar64Vec3),//signed velocity component along a direction
+}
+//the effects of a part only happen when the trap condition is met
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub struct Trap{
+	pub measure:TrapMeasure,
+	pub condition:TrapCondition,
+}
+impl Trap{
+	pub fn evaluate(&self,velocity:Planar64Vec3)->bool{
+		match &self.measure{
+			TrapMeasure::Speed=>{
+				//compare squared speeds so there is no rounding
+				let speed_squared=velocity.dot128(velocity);
+				self.condition.evaluate_by(|threshold|{
+					if threshold<Planar64::ZERO{
+						std::cmp::Ordering::Greater
+					}else{
+						speed_squared.cmp(&(threshold.get() as i128*threshold.get() as i128))
+					}
+				})
+			},
+			&TrapMeasure::Velocity(direction)=>{
+				if direction==Planar64Vec3::ZERO{
+					return self.condition.evaluate(Planar64::ZERO);
+				}
+				self.condition.evaluate(velocity.dot(direction.with_length(Planar64::ONE)))
+			},
+		}
+	}
 }
-// enum TrapCondition{
-// 	FasterThan(Planar64),
-// 	SlowerThan(Planar64),
-// 	InRange(Planar64,Planar64),
-// 	OutsideRange(Planar64,Planar64),
-// }
 #[derive(Clone,Hash,Eq,PartialEq)]
 pub struct Wormhole{
 	//destination does not need to be another wormhole
 	//this defines a one way portal to a destination model transform
 	//two of these can create a two way wormhole
-	pub destination_model_id:u32,
+	pub destination_model_id:ModelId,
 	//(position,angles)*=origin.transform.inverse()*destination.transform
 }
+#[derive(Debug)]
+pub enum WormholeError{
+	MissingDestination(ModelId),
+	//the origin transform has no inverse, e.g. it is scaled to zero along an axis
+	SingularOrigin,
+}
+impl Wormhole{
+	//models are indexed by ModelId
+	pub fn destination<'a>(&self,models:&'a [Model])->Result<&'a Model,WormholeError>{
+		models.get(self.destination_model_id.get() as usize)
+		.ok_or(WormholeError::MissingDestination(self.destination_model_id))
+	}
+	pub fn transform(&self,origin:&Model,models:&[Model])->Result<WormholeTransform,WormholeError>{
+		WormholeTransform::new(&origin.transform,&self.destination(models)?.transform)
+	}
+}
+//maps things near the origin model to the same relative place at the destination model
+pub struct WormholeTransform{
+	transform:Planar64Affine3,
+}
+impl WormholeTransform{
+	pub fn new(origin:&Planar64Affine3,destination:&Planar64Affine3)->Result<Self,WormholeError>{
+		//inverse divides by the determinant
+		if origin.matrix3.determinant()==Planar64::ZERO{
+			return Err(WormholeError::SingularOrigin);
+		}
+		let matrix3=destination.matrix3*origin.matrix3.inverse();
+		Ok(Self{
+			transform:Planar64Affine3::new(matrix3,destination.translation-matrix3*origin.translation),
+		})
+	}
+	#[inline]
+	pub fn transform_position(&self,position:Planar64Vec3)->Planar64Vec3{
+		self.transform.transform_point3(position)
+	}
+	//rotated with the portal, but speed is preserved even if the models are scaled differently
+	pub fn transform_velocity(&self,velocity:Planar64Vec3)->Planar64Vec3{
+		let rotated=self.transform.matrix3*velocity;
+		if rotated==Planar64Vec3::ZERO{
+			return Planar64Vec3::ZERO;
+		}
+		rotated.with_length(velocity.length())
+	}
+	//camera (yaw,pitch) looking down -z, the camera never rolls so any roll from the portal is discarded
+	pub fn transform_angles(&self,yaw:Angle32,pitch:Angle32)->(Angle32,Angle32){
+		let look=self.transform.matrix3*(Planar64Mat3::from_rotation_yx(yaw,pitch)*Planar64Vec3::NEG_Z);
+		if look==Planar64Vec3::ZERO{
+			return (yaw,pitch);
+		}
+		let (x,y,z)=(look.x().get() as f64,look.y().get() as f64,look.z().get() as f64);
+		(
+			Angle32::wrap_from_radians((-x).atan2(-z)),
+			Angle32::clamp_from_radians(y.atan2(x.hypot(z))),
+		)
+	}
+	//acceleration is left alone because it is world space gravity
+	pub fn transform_body(&self,body:&Body)->Body{
+		Body::new(
+			self.transform_position(body.position),
+			self.transform_velocity(body.velocity),
+			body.acceleration,
+			body.time,
+		)
+	}
+}
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub enum RespawnDestination{
+	StageSpawn,//kill brick, back to the spawn of the current stage
+	Restart,//reset the run and go back to the first stage
+}
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub struct Respawn{
+	pub destination:RespawnDestination,
+	pub reset_velocity:bool,
+}
+impl Respawn{
+	#[inline]
+	pub fn resets_run(&self)->bool{
+		matches!(self.destination,RespawnDestination::Restart)
+	}
+	pub fn spawn_model_id(&self,mode:&Mode,current_stage:StageId)->Option<ModelId>{
+		match self.destination{
+			RespawnDestination::StageSpawn=>mode.get_spawn_model_id(current_stage),
+			RespawnDestination::Restart=>mode.get_spawn_model_id(StageId::FIRST),
+		}
+	}
+}
 //attributes listed in order of handling
 #[derive(Default,Clone,Hash,Eq,PartialEq)]
 pub struct GeneralAttributes{
+	pub trap:Option<Trap>,//gates every other general attribute
+	pub respawn:Option<Respawn>,
 	pub booster:Option<Booster>,
 	pub trajectory:Option<SetTrajectory>,
 	pub wormhole:Option<Wormhole>,
@@ -89,48 +437,195 @@ pub struct GeneralAttributes{
 }
 impl GeneralAttributes{
 	pub fn any(&self)->bool{
-		self.booster.is_some()
+		self.respawn.is_some()
+		||self.booster.is_some()
 		||self.trajectory.is_some()
 		||self.wormhole.is_some()
 		||self.accelerator.is_some()
 	}
-	pub fn is_wrcp(&self)->bool{
-		self.trajectory.as_ref().map_or(false,|t|t.is_velocity())
-		/*
-		&&match &self.teleport_behaviour{
-			Some(TeleportBehaviour::StageElement(
-				StageElement{
-					mode_id,
-					stage_id:_,
-					force:true,
-					behaviour:StageElementBehaviour::Trigger|StageElementBehaviour::Teleport
-				}
-			))=>current_mode_id==*mode_id,
-			_=>false,
+	//whether the effects apply to a body moving at this velocity
+	pub fn is_triggered(&self,velocity:Planar64Vec3)->bool{
+		self.trap.as_ref().is_none_or(|trap|trap.evaluate(velocity))
+	}
+	//a velocity-setting forced Trigger or Teleport starts a stage cleanly,
+	//so the stage time that follows can count for the stage record
+	pub fn is_wrcp(&self,element:Option<&StageElement>)->bool{
+		self.trajectory.as_ref().is_some_and(|t|t.is_velocity())
+		&&element.is_some_and(|element|element.force()&&matches!(element.behaviour(),
+			StageElementBehaviour::Trigger|StageElementBehaviour::Teleport
+		))
+	}
+}
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub enum FrictionCombine{
+	Replace,//the surface value is used instead of the style value
+	Multiply,//the surface value scales the style value e.g. ice is a small multiplier
+}
+//per-surface friction, unset values fall through to the style
+#[derive(Clone,Hash,Eq,PartialEq)]
+pub struct ContactingFriction{
+	pub static_friction:Option<Planar64>,
+	pub kinetic_friction:Option<Planar64>,
+	pub combine:FrictionCombine,
+}
+impl ContactingFriction{
+	fn combine(&self,style_value:Planar64,surface_value:Option<Planar64>)->Planar64{
+		match (surface_value,&self.combine){
+			(None,_)=>style_value,
+			(Some(surface_value),FrictionCombine::Replace)=>surface_value,
+			(Some(surface_value),FrictionCombine::Multiply)=>style_value*surface_value,
 		}
-		*/
+	}
+}
+//slow the velocity relative to the surface along the surface.
+//friction decelerates by kinetic_friction times the part of gravity pressing into the surface.
+//surface_velocity is how fast the surface itself moves, e.g. a conveyor.
+pub fn apply_friction(velocity:Planar64Vec3,surface_velocity:Planar64Vec3,normal:Planar64Vec3,kinetic_friction:Planar64,gravity:Planar64Vec3,dt:Time)->Planar64Vec3{
+	let normal_length=normal.length();
+	if normal_length==Planar64::ZERO{
+		return velocity;
+	}
+	let normal_acceleration=-gravity.dot(normal)/normal_length;
+	if normal_acceleration<=Planar64::ZERO{
+		return velocity;
+	}
+	let relative=reject(velocity-surface_velocity,normal);
+	let relative_speed=relative.length();
+	let deceleration=kinetic_friction*normal_acceleration*dt;
+	if relative_speed<=deceleration{
+		//friction brings it to rest on the surface
+		velocity-relative
+	}else{
+		velocity-relative+relative.with_length(relative_speed-deceleration)
 	}
 }
 #[derive(Default,Clone,Hash,Eq,PartialEq)]
 pub struct ContactingAttributes{
-	//friction?
+	pub friction:Option<ContactingFriction>,
+	pub surface_velocity:Option<Planar64Vec3>,//conveyor belts and treadmills, the part does not actually move
 	pub contact_behaviour:Option<ContactingBehaviour>,
 }
 impl ContactingAttributes{
 	pub fn any(&self)->bool{
-		self.contact_behaviour.is_some()
+		self.friction.is_some()
+		||self.surface_velocity.is_some()
+		||self.contact_behaviour.is_some()
+	}
+	pub fn static_friction(&self,style:&StyleModifiers)->Planar64{
+		match &self.friction{
+			Some(friction)=>friction.combine(style.static_friction(),friction.static_friction),
+			None=>style.static_friction(),
+		}
+	}
+	pub fn kinetic_friction(&self,style:&StyleModifiers)->Planar64{
+		match &self.friction{
+			Some(friction)=>friction.combine(style.kinetic_friction(),friction.kinetic_friction),
+			None=>style.kinetic_friction(),
+		}
+	}
+	//only the part of the surface velocity along the surface counts
+	pub fn surface_velocity(&self,normal:Planar64Vec3)->Planar64Vec3{
+		match self.surface_velocity{
+			Some(surface_velocity)=>reject(surface_velocity,normal),
+			None=>Planar64Vec3::ZERO,
+		}
+	}
+	pub fn apply_friction(&self,velocity:Planar64Vec3,normal:Planar64Vec3,style:&StyleModifiers,dt:Time)->Planar64Vec3{
+		apply_friction(velocity,self.surface_velocity(normal),normal,self.kinetic_friction(style),style.gravity(),dt)
+	}
+	//the velocity walking settles at, carried along by the surface
+	pub fn walk_target_velocity(&self,control_dir:Planar64Vec3,normal:Planar64Vec3,style:&StyleModifiers)->Planar64Vec3{
+		let walk_dir=reject(control_dir,normal);
+		if walk_dir==Planar64Vec3::ZERO{
+			self.surface_velocity(normal)
+		}else{
+			self.surface_velocity(normal)+walk_dir.with_length(style.walk_speed())
+		}
+	}
+	//accelerate along the surface towards the walk target velocity.
+	//the acceleration is walk_accel, unless the surface can't grip that hard:
+	//traction is limited to static friction times the gravity pressing into the surface.
+	pub fn walk_accelerate(&self,velocity:Planar64Vec3,control_dir:Planar64Vec3,normal:Planar64Vec3,style:&StyleModifiers,dt:Time)->Planar64Vec3{
+		let normal_length=normal.length();
+		if normal_length==Planar64::ZERO{
+			return velocity;
+		}
+		let traction=self.static_friction(style)*(-style.gravity().dot(normal)/normal_length);
+		let accel=style.walk_accel().min(traction.max(Planar64::ZERO));
+		let target_diff=reject(self.walk_target_velocity(control_dir,normal,style)-velocity,normal);
+		let diff_length=target_diff.length();
+		let max_change=accel*dt;
+		if diff_length<=max_change{
+			velocity+target_diff
+		}else{
+			velocity+target_diff.with_length(max_change)
+		}
+	}
+	pub fn contact_velocity(&self,velocity:Planar64Vec3,normal:Planar64Vec3,style:&StyleModifiers)->Planar64Vec3{
+		match &self.contact_behaviour{
+			Some(contact_behaviour)=>contact_behaviour.contact_velocity(velocity,normal,style),
+			None=>clip_velocity(velocity,normal),
+		}
 	}
 }
 #[derive(Default,Clone,Hash,Eq,PartialEq)]
 pub struct IntersectingAttributes{
 	pub water:Option<IntersectingWater>,
+	pub gravity:Option<IntersectingGravity>,
+	pub style:Option<IntersectingStyle>,
 }
 impl IntersectingAttributes{
 	pub fn any(&self)->bool{
 		self.water.is_some()
+		||self.gravity.is_some()
+		||self.style.is_some()
 	}
 }
+//Stacking rules for overlapping zones:
+//style overrides are applied from lowest to highest priority, so each value comes from
+//the highest priority zone which sets it. no_strafe and no_jump apply if any zone sets them.
+//gravity starts from the resulting style, the highest priority Replace zone replaces it
+//and then every Add zone is added on top.
+//equal priorities are resolved by iteration order with the later zone winning,
+//so iterate zones in a deterministic order such as by ModelId.
+pub fn resolve_style<'a,I:IntoIterator<Item=&'a IntersectingAttributes>>(style:&StyleModifiers,zones:I)->StyleModifiers{
+	let mut style_zones:Vec<&IntersectingStyle>=zones.into_iter().filter_map(|zone|zone.style.as_ref()).collect();
+	//stable sort keeps iteration order for equal priorities
+	style_zones.sort_by_key(|zone|zone.priority);
+	let mut style=style.clone();
+	for zone in style_zones{
+		style=style.with_override(&zone.modifiers);
+	}
+	style
+}
+pub fn resolve_gravity<'a,I:IntoIterator<Item=&'a IntersectingAttributes>>(gravity:Planar64Vec3,zones:I)->Planar64Vec3{
+	let mut replace:Option<(u32,Planar64Vec3)>=None;
+	let mut add=Planar64Vec3::ZERO;
+	for zone in zones{
+		match &zone.gravity{
+			Some(IntersectingGravity{behaviour:GravityBehaviour::Replace(gravity),priority})
+				if replace.is_none_or(|(replace_priority,_)|replace_priority<=*priority)=>{
+				replace=Some((*priority,*gravity));
+			},
+			Some(IntersectingGravity{behaviour:GravityBehaviour::Add(gravity),priority:_})=>add+=*gravity,
+			_=>(),
+		}
+	}
+	replace.map_or(gravity,|(_,gravity)|gravity)+add
+}
+#[derive(Clone,Copy,Debug,Hash,Eq,PartialEq)]
 pub struct CollisionAttributesId(u32);
+impl CollisionAttributesId{
+	#[inline]
+	pub const fn new(id:u32)->Self{
+		Self(id)
+	}
+	#[inline]
+	pub const fn get(&self)->u32{
+		self.0
+	}
+}
+#[derive(Clone,Hash,Eq,PartialEq)]
 pub enum CollisionAttributes{
 	Decoration,//visual only
 	Contact{//track whether you are contacting the object
@@ -142,6 +637,15 @@ pub enum CollisionAttributes{
 		general:GeneralAttributes,
 	},
 }
+impl CollisionAttributes{
+	pub fn general(&self)->Option<&GeneralAttributes>{
+		match self{
+			CollisionAttributes::Decoration=>None,
+			CollisionAttributes::Contact{contacting:_,general}
+			|CollisionAttributes::Intersect{intersecting:_,general}=>Some(general),
+		}
+	}
+}
 impl std::default::Default for CollisionAttributes{
 	fn default()->Self{
 		Self::Contact{
@@ -150,3 +654,133 @@ impl std::default::Default for CollisionAttributes{
 		}
 	}
 }
+
+//identical attributes are stored once and shared by every model which uses them
+#[derive(Default)]
+pub struct CollisionAttributesRegistry{
+	attributes:Vec<CollisionAttributes>,
+	usage_counts:Vec<u32>,
+	ids:HashMap<CollisionAttributes,CollisionAttributesId>,
+}
+impl CollisionAttributesRegistry{
+	pub fn new()->Self{
+		Self::default()
+	}
+	//counts one more use of the attributes
+	pub fn intern(&mut self,attributes:CollisionAttributes)->CollisionAttributesId{
+		let id=match self.ids.get(&attributes){
+			Some(&id)=>id,
+			None=>{
+				let id=CollisionAttributesId(self.attributes.len() as u32);
+				self.ids.insert(attributes.clone(),id);
+				self.attributes.push(attributes);
+				self.usage_counts.push(0);
+				id
+			},
+		};
+		self.usage_counts[id.0 as usize]+=1;
+		id
+	}
+	#[inline]
+	pub fn get(&self,id:CollisionAttributesId)->Option<&CollisionAttributes>{
+		self.attributes.get(id.0 as usize)
+	}
+	//the effective attributes of a model in a mode, taking per-mode overrides into account
+	#[inline]
+	pub fn resolve(&self,model:&Model,mode:ModeId)->Option<&CollisionAttributes>{
+		self.get(model.attributes_for_mode(mode))
+	}
+	#[inline]
+	pub fn usage_count(&self,id:CollisionAttributesId)->u32{
+		self.usage_counts.get(id.0 as usize).copied().unwrap_or(0)
+	}
+	#[inline]
+	pub fn len(&self)->usize{
+		self.attributes.len()
+	}
+	#[inline]
+	pub fn is_empty(&self)->bool{
+		self.attributes.is_empty()
+	}
+	pub fn iter(&self)->impl Iterator<Item=(CollisionAttributesId,&CollisionAttributes)>{
+		self.attributes.iter().enumerate().map(|(i,attributes)|(CollisionAttributesId(i as u32),attributes))
+	}
+	pub fn clear(&mut self){
+		self.attributes.clear();
+		self.usage_counts.clear();
+		self.ids.clear();
+	}
+}
+
+#[test]
+fn test_target_point_time(){
+	let position=Planar64Vec3::int(1,2,3);
+	let target_point=Planar64Vec3::int(40,-10,20);
+	let gravity=Planar64Vec3::int(0,-100,0);
+	let time=Time::from_millis(1500);
+	let velocity=SetTrajectory::TargetPointTime{target_point,time}.velocity(position,Planar64Vec3::ZERO,gravity);
+	let body=crate::body::Body::new(position,velocity,gravity,Time::ZERO);
+	let miss=body.extrapolated_position(time)-target_point;
+	assert!(miss.length()<Planar64::raw(1<<12));
+}
+#[test]
+fn test_target_point_speed_too_slow(){
+	let gravity=Planar64Vec3::int(0,-100,0);
+	let velocity=Planar64Vec3::int(1,1,1);
+	let launch=|target_point,speed|SetTrajectory::TargetPointSpeed{
+		target_point,
+		speed:Planar64::int(speed),
+		trajectory_choice:TrajectoryChoice::LowArcShortDuration,
+	}.velocity(Planar64Vec3::ZERO,velocity,gravity);
+	//straight up gets closest to a target directly above
+	assert!(launch(Planar64Vec3::int(0,100,0),10)==Planar64Vec3::int(0,10,0));
+	//aimed between the target and straight up, at the given speed
+	let closest=launch(Planar64Vec3::int(30,20,0),40);
+	assert!((closest.length()-Planar64::int(40)).get().abs()<1<<8);
+	assert!(Planar64::ZERO<closest.x()&&closest.x()<closest.y()&&closest.z()==Planar64::ZERO);
+}
+#[test]
+fn test_set_height(){
+	let gravity=Planar64Vec3::int(0,-100,0);
+	let velocity=Planar64Vec3::int(3,-7,4);
+	let up=SetTrajectory::Height(Planar64::int(5)).velocity(Planar64Vec3::ZERO,velocity,gravity);
+	//v=sqrt(2*100*5)
+	assert!(up==Planar64Vec3::int(3,0,4)+Planar64Vec3::Y*Planar64::int(1000).sqrt());
+	for height in [Planar64::ZERO,Planar64::int(-5)]{
+		assert!(SetTrajectory::Height(height).velocity(Planar64Vec3::ZERO,velocity,gravity)==Planar64Vec3::int(3,0,4));
+	}
+}
+#[test]
+fn test_energy_booster(){
+	let boost=|speed:i32,energy:i32|Booster::Energy{direction:Planar64Vec3::int(0,2,0),energy:Planar64::int(energy)}
+		.boost(Planar64Vec3::int(3,speed,0),Planar64::ONE);
+	//10^2+2*50=200
+	assert!(boost(10,50)==Planar64Vec3::int(3,0,0)+Planar64Vec3::Y*Planar64::int(200).sqrt());
+	//against the boost the energy is spent slowing down first
+	assert!(boost(-10,50)==Planar64Vec3::int(3,0,0));
+	assert!(boost(-10,150)==Planar64Vec3::int(3,0,0)+Planar64Vec3::Y*Planar64::int(200).sqrt());
+	//negative energy slows down and then pushes the other way
+	assert!(boost(10,-50)==Planar64Vec3::int(3,0,0));
+	assert!(boost(10,-150)==Planar64Vec3::int(3,0,0)-Planar64Vec3::Y*Planar64::int(200).sqrt());
+	assert!(boost(-10,-50)==Planar64Vec3::int(3,0,0)-Planar64Vec3::Y*Planar64::int(200).sqrt());
+}
+#[test]
+fn test_wormhole_transform(){
+	let origin=Planar64Affine3::new(Planar64Mat3::from_diagonal(Planar64Vec3::int(2,2,2)),Planar64Vec3::int(10,0,0));
+	let destination=Planar64Affine3::new(Planar64Mat3::from_diagonal(Planar64Vec3::int(1,1,1)),Planar64Vec3::int(0,5,0));
+	let transform=WormholeTransform::new(&origin,&destination).unwrap();
+	assert!(transform.transform_position(Planar64Vec3::int(12,0,0))==Planar64Vec3::int(1,5,0));
+	let flat=Planar64Affine3::new(Planar64Mat3::from_diagonal(Planar64Vec3::int(1,0,1)),Planar64Vec3::ZERO);
+	assert!(matches!(WormholeTransform::new(&flat,&destination),Err(WormholeError::SingularOrigin)));
+}
+#[test]
+fn test_registry_interning(){
+	let mut registry=CollisionAttributesRegistry::new();
+	let decoration=registry.intern(CollisionAttributes::Decoration);
+	let contact=registry.intern(CollisionAttributes::default());
+	assert_eq!(registry.intern(CollisionAttributes::Decoration),decoration);
+	assert_ne!(decoration,contact);
+	assert_eq!(registry.len(),2);
+	assert_eq!(registry.usage_count(decoration),2);
+	assert_eq!(registry.usage_count(contact),1);
+}
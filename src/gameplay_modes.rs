@@ -2,13 +2,36 @@ use std::collections::{HashSet,HashMap};
 use crate::model::ModelId;
 use crate::gameplay_style;
 
+#[derive(Clone,Debug,Hash,Eq,PartialEq)]
 pub struct StageElement{
 	stage:StageId,//which stage spawn to send to
 	force:bool,//allow setting to lower spawn id i.e. 7->3
 	behaviour:StageElementBehaviour
 }
+impl StageElement{
+	#[inline]
+	pub const fn new(stage:StageId,force:bool,behaviour:StageElementBehaviour)->Self{
+		Self{
+			stage,
+			force,
+			behaviour,
+		}
+	}
+	#[inline]
+	pub const fn stage(&self)->StageId{
+		self.stage
+	}
+	#[inline]
+	pub const fn force(&self)->bool{
+		self.force
+	}
+	#[inline]
+	pub fn behaviour(&self)->&StageElementBehaviour{
+		&self.behaviour
+	}
+}
 
-#[derive(Clone,Hash,Eq,PartialEq)]
+#[derive(Clone,Debug,Hash,Eq,PartialEq)]
 pub enum StageElementBehaviour{
 	SpawnAt,//must be standing on top to get effect. except cancollide false
 	Trigger,
@@ -20,21 +43,133 @@ pub enum StageElementBehaviour{
 	Checkpoint,//this is a combined behaviour for Ordered & Unordered in case a model is used multiple times or for both.
 }
 
+#[derive(Clone,Copy,Debug,Hash,Eq,PartialEq,Ord,PartialOrd)]
 pub struct StageId(u32);
+impl StageId{
+	pub const FIRST:Self=Self(0);
+	#[inline]
+	pub const fn new(id:u32)->Self{
+		Self(id)
+	}
+	#[inline]
+	pub const fn get(&self)->u32{
+		self.0
+	}
+}
 pub struct Stage{
 	spawn:ModelId,
 	//other behaviour models of this stage can have
 	ordered_checkpoints:Vec<ModelId>,
 	unordered_checkpoints:HashSet<ModelId>,
+	//elements which are not implied by the above, (behaviour,force)
+	elements:HashMap<ModelId,(StageElementBehaviour,bool)>,
+}
+impl Stage{
+	pub fn new(spawn:ModelId)->Self{
+		Self{
+			spawn,
+			ordered_checkpoints:Vec::new(),
+			unordered_checkpoints:HashSet::new(),
+			elements:HashMap::new(),
+		}
+	}
+	#[inline]
+	pub fn spawn(&self)->ModelId{
+		self.spawn
+	}
+	#[inline]
+	pub fn ordered_checkpoints(&self)->&[ModelId]{
+		&self.ordered_checkpoints
+	}
+	#[inline]
+	pub fn unordered_checkpoints(&self)->&HashSet<ModelId>{
+		&self.unordered_checkpoints
+	}
+	#[inline]
+	pub fn push_ordered_checkpoint(&mut self,model_id:ModelId){
+		self.ordered_checkpoints.push(model_id);
+	}
+	#[inline]
+	pub fn insert_unordered_checkpoint(&mut self,model_id:ModelId)->bool{
+		self.unordered_checkpoints.insert(model_id)
+	}
+	//(model_id,behaviour,force)
+	pub fn elements(&self)->impl Iterator<Item=(ModelId,&StageElementBehaviour,bool)>{
+		self.elements.iter().map(|(&model_id,(behaviour,force))|(model_id,behaviour,*force))
+	}
+	#[inline]
+	pub fn insert_element(&mut self,model_id:ModelId,behaviour:StageElementBehaviour,force:bool)->Option<(StageElementBehaviour,bool)>{
+		self.elements.insert(model_id,(behaviour,force))
+	}
 }
 
-#[derive(Clone,Hash,Eq,PartialEq)]
+#[derive(Clone,Debug,Hash,Eq,PartialEq)]
 pub enum ZoneBehaviour{
+	//ends the run when all checkpoints of the current stage are hit
 	Finish,
-	Anitcheat,
+	//invalidates the run timer on touch.
+	//movement is unaffected, the player keeps playing but the run cannot be submitted
+	Anticheat,
+}
+impl ZoneBehaviour{
+	#[deprecated(note="use ZoneBehaviour::Anticheat")]
+	#[allow(non_upper_case_globals)]
+	pub const Anitcheat:Self=Self::Anticheat;
 }
+#[derive(Debug)]
+pub struct ParseZoneBehaviourError(String);
+impl std::fmt::Display for ParseZoneBehaviourError{
+	fn fmt(&self,f:&mut std::fmt::Formatter<'_>)->std::fmt::Result{
+		write!(f,"unknown zone behaviour {:?}",self.0)
+	}
+}
+impl std::error::Error for ParseZoneBehaviourError{}
+impl std::str::FromStr for ZoneBehaviour{
+	type Err=ParseZoneBehaviourError;
+	fn from_str(s:&str)->Result<Self,Self::Err>{
+		match s{
+			"Finish"=>Ok(Self::Finish),
+			//old maps were saved with the misspelling
+			"Anticheat"|"Anitcheat"=>Ok(Self::Anticheat),
+			_=>Err(ParseZoneBehaviourError(s.to_owned())),
+		}
+	}
+}
+#[derive(Clone,Copy,Debug,Hash,Eq,PartialEq)]
 pub struct ModeId(u32);
+impl ModeId{
+	pub const MAIN:Self=Self(0);
+	#[inline]
+	pub const fn new(id:u32)->Self{
+		Self(id)
+	}
+	#[inline]
+	pub const fn get(&self)->u32{
+		self.0
+	}
+}
+#[derive(Clone,Copy,Debug,Hash,Eq,PartialEq)]
+pub enum ModeKind{
+	Main,
+	Bonus(u32),
+	//not timed for leaderboards
+	Practice,
+}
+impl std::fmt::Display for ModeKind{
+	fn fmt(&self,f:&mut std::fmt::Formatter<'_>)->std::fmt::Result{
+		match self{
+			ModeKind::Main=>write!(f,"Main"),
+			ModeKind::Bonus(bonus)=>write!(f,"Bonus {bonus}"),
+			ModeKind::Practice=>write!(f,"Practice"),
+		}
+	}
+}
 pub struct Mode{
+	name:String,
+	kind:ModeKind,
+	description:String,
+	//None allows every style
+	allowed_styles:Option<Vec<gameplay_style::StylePreset>>,
 	style:gameplay_style::StyleModifiers,
 	start:ModelId,
 	zones:HashMap<ModelId,ZoneBehaviour>,
@@ -42,34 +177,414 @@ pub struct Mode{
 	//mutually exlusive stage element behaviour
 	elements:HashMap<ModelId,StageElement>,
 	jump_limit:HashMap<ModelId,u32>,
+	//None when stages or elements changed since the indices were last built
+	indices:Option<ModeIndices>,
+}
+//reverse lookups for tools and the client, built from the elements and stages of a Mode
+#[derive(Default)]
+pub struct ModeIndices{
+	stage_models:Vec<Vec<ModelId>>,
+	ordered_checkpoint_index:HashMap<ModelId,usize>,
+}
+impl ModeIndices{
+	fn build(stages:&[Stage],elements:&HashMap<ModelId,StageElement>)->Self{
+		let mut stage_models=vec![Vec::new();stages.len()];
+		for (&model_id,element) in elements{
+			if let Some(models)=stage_models.get_mut(element.stage.0 as usize){
+				models.push(model_id);
+			}
+		}
+		for models in &mut stage_models{
+			models.sort_by_key(ModelId::get);
+		}
+		let mut ordered_checkpoint_index=HashMap::new();
+		for stage in stages{
+			for (index,&model_id) in stage.ordered_checkpoints.iter().enumerate(){
+				ordered_checkpoint_index.entry(model_id).or_insert(index);
+			}
+		}
+		Self{
+			stage_models,
+			ordered_checkpoint_index,
+		}
+	}
+	//every model with an element for the stage, sorted by id
+	pub fn get_stage_models(&self,stage:StageId)->&[ModelId]{
+		self.stage_models.get(stage.0 as usize).map_or(&[],Vec::as_slice)
+	}
+	//position of the model in the ordered checkpoints of its stage
+	#[inline]
+	pub fn get_ordered_checkpoint_index(&self,model_id:ModelId)->Option<usize>{
+		self.ordered_checkpoint_index.get(&model_id).copied()
+	}
 }
 impl Mode{
+	pub fn new(style:gameplay_style::StyleModifiers,start:ModelId)->Self{
+		Self{
+			name:String::new(),
+			kind:ModeKind::Main,
+			description:String::new(),
+			allowed_styles:None,
+			style,
+			start,
+			zones:HashMap::new(),
+			stages:Vec::new(),
+			elements:HashMap::new(),
+			jump_limit:HashMap::new(),
+			indices:Some(ModeIndices::default()),
+		}
+	}
+	#[inline]
+	pub fn name(&self)->&str{
+		&self.name
+	}
+	#[inline]
+	pub fn set_name(&mut self,name:String){
+		self.name=name;
+	}
+	#[inline]
+	pub fn kind(&self)->ModeKind{
+		self.kind
+	}
+	#[inline]
+	pub fn set_kind(&mut self,kind:ModeKind){
+		self.kind=kind;
+	}
+	#[inline]
+	pub fn description(&self)->&str{
+		&self.description
+	}
+	#[inline]
+	pub fn set_description(&mut self,description:String){
+		self.description=description;
+	}
+	#[inline]
+	pub fn allowed_styles(&self)->Option<&[gameplay_style::StylePreset]>{
+		self.allowed_styles.as_deref()
+	}
+	#[inline]
+	pub fn set_allowed_styles(&mut self,allowed_styles:Option<Vec<gameplay_style::StylePreset>>){
+		self.allowed_styles=allowed_styles;
+	}
+	pub fn is_style_allowed(&self,preset:gameplay_style::StylePreset)->bool{
+		self.allowed_styles.as_ref().is_none_or(|allowed_styles|allowed_styles.contains(&preset))
+	}
+	#[inline]
+	pub fn style(&self)->&gameplay_style::StyleModifiers{
+		&self.style
+	}
+	#[inline]
+	pub fn get_start_model_id(&self)->ModelId{
+		self.start
+	}
 	pub fn get_spawn_model_id(&self,stage:StageId)->Option<ModelId>{
 		self.stages.get(stage.0 as usize).map(|s|s.spawn)
 	}
-	pub fn denormalize_data(&mut self){
-		//expand and index normalized data
+	#[inline]
+	pub fn get_stage(&self,stage:StageId)->Option<&Stage>{
+		self.stages.get(stage.0 as usize)
+	}
+	#[inline]
+	pub fn get_stage_mut(&mut self,stage:StageId)->Option<&mut Stage>{
+		self.indices=None;
+		self.stages.get_mut(stage.0 as usize)
+	}
+	#[inline]
+	pub fn get_zone(&self,model_id:ModelId)->Option<&ZoneBehaviour>{
+		self.zones.get(&model_id)
+	}
+	#[inline]
+	pub fn is_anticheat(&self,model_id:ModelId)->bool{
+		self.zones.get(&model_id)==Some(&ZoneBehaviour::Anticheat)
+	}
+	pub fn anticheat_zones(&self)->impl Iterator<Item=ModelId>+'_{
+		self.zones.iter().filter(|&(_,zone)|*zone==ZoneBehaviour::Anticheat).map(|(&model_id,_)|model_id)
+	}
+	pub fn finish_zones(&self)->impl Iterator<Item=ModelId>+'_{
+		self.zones.iter().filter(|&(_,zone)|*zone==ZoneBehaviour::Finish).map(|(&model_id,_)|model_id)
+	}
+	#[inline]
+	pub fn get_element(&self,model_id:ModelId)->Option<&StageElement>{
+		self.elements.get(&model_id)
+	}
+	#[inline]
+	pub fn get_jump_limit(&self,model_id:ModelId)->Option<u32>{
+		self.jump_limit.get(&model_id).copied()
+	}
+	pub fn stages(&self)->impl Iterator<Item=(StageId,&Stage)>{
+		self.stages.iter().enumerate().map(|(stage_id,stage)|(StageId(stage_id as u32),stage))
+	}
+	pub fn zones(&self)->impl Iterator<Item=(ModelId,&ZoneBehaviour)>{
+		self.zones.iter().map(|(&model_id,zone)|(model_id,zone))
+	}
+	pub fn elements(&self)->impl Iterator<Item=(ModelId,&StageElement)>{
+		self.elements.iter().map(|(&model_id,element)|(model_id,element))
+	}
+	pub fn jump_limits(&self)->impl Iterator<Item=(ModelId,u32)>+'_{
+		self.jump_limit.iter().map(|(&model_id,&limit)|(model_id,limit))
+	}
+	pub fn push_stage(&mut self,stage:Stage)->StageId{
+		let stage_id=StageId(self.stages.len() as u32);
+		self.indices=None;
+		self.stages.push(stage);
+		stage_id
+	}
+	#[inline]
+	pub fn insert_zone(&mut self,model_id:ModelId,zone:ZoneBehaviour)->Option<ZoneBehaviour>{
+		self.zones.insert(model_id,zone)
+	}
+	pub fn insert_element(&mut self,model_id:ModelId,element:StageElement)->Option<StageElement>{
+		self.indices=None;
+		//record it in the normalized stages too so denormalize_data keeps it and validate sees it
+		if let Some(old)=self.elements.get(&model_id){
+			if let Some(stage)=self.stages.get_mut(old.stage.0 as usize){
+				stage.elements.remove(&model_id);
+			}
+		}
+		if let Some(stage)=self.stages.get_mut(element.stage.0 as usize){
+			stage.insert_element(model_id,element.behaviour.clone(),element.force);
+		}
+		self.elements.insert(model_id,element)
+	}
+	#[inline]
+	pub fn insert_jump_limit(&mut self,model_id:ModelId,limit:u32)->Option<u32>{
+		self.jump_limit.insert(model_id,limit)
+	}
+	//spawns and checkpoints, later stages overwrite earlier ones
+	fn implied_elements(&self)->HashMap<ModelId,StageElement>{
+		let mut elements=HashMap::new();
 		for (stage_id,stage) in self.stages.iter().enumerate(){
-			self.elements.insert(stage.spawn,StageElement{
+			elements.insert(stage.spawn,StageElement{
 				stage:StageId(stage_id as u32),
 				force:false,
 				behaviour:StageElementBehaviour::SpawnAt,
 			});
 			for &model_id in &stage.ordered_checkpoints{
-				self.elements.insert(model_id,StageElement{
+				elements.insert(model_id,StageElement{
 					stage:StageId(stage_id as u32),
 					force:false,
 					behaviour:StageElementBehaviour::Checkpoint,
 				});
 			}
 			for &model_id in &stage.unordered_checkpoints{
-				self.elements.insert(model_id,StageElement{
+				elements.insert(model_id,StageElement{
 					stage:StageId(stage_id as u32),
 					force:false,
 					behaviour:StageElementBehaviour::Checkpoint,
 				});
 			}
 		}
+		elements
+	}
+	pub fn denormalize_data(&mut self){
+		//expand and index normalized data
+		let implied=self.implied_elements();
+		self.elements.extend(implied);
+		//explicit stage elements take precedence over spawns and checkpoints
+		for (stage_id,stage) in self.stages.iter().enumerate(){
+			for (&model_id,(behaviour,force)) in &stage.elements{
+				self.elements.insert(model_id,StageElement{
+					stage:StageId(stage_id as u32),
+					force:*force,
+					behaviour:behaviour.clone(),
+				});
+			}
+		}
+		self.rebuild_indices();
+	}
+	//denormalize_data does this, call it directly after editing elements of a denormalized mode
+	pub fn rebuild_indices(&mut self){
+		self.indices=Some(ModeIndices::build(&self.stages,&self.elements));
+	}
+	//None after push_stage, insert_element or get_stage_mut until the indices are rebuilt
+	#[inline]
+	pub fn indices(&self)->Option<&ModeIndices>{
+		self.indices.as_ref()
+	}
+	//the stage of the element on the model
+	#[inline]
+	pub fn get_model_stage(&self,model_id:ModelId)->Option<StageId>{
+		self.elements.get(&model_id).map(StageElement::stage)
+	}
+	//the checkpoint the player needs next given the ordered checkpoints hit so far,
+	//ordered checkpoints come first and then the lowest unordered one not yet hit
+	pub fn get_next_checkpoint(&self,stage:StageId,ordered_progress:usize,unordered_hit:&HashSet<ModelId>)->Option<ModelId>{
+		let stage=self.get_stage(stage)?;
+		stage.ordered_checkpoints.get(ordered_progress).copied().or_else(||
+			stage.unordered_checkpoints.iter()
+			.filter(|model_id|!unordered_hit.contains(model_id))
+			.min_by_key(|model_id|model_id.get())
+			.copied()
+		)
+	}
+	//the reverse of denormalize_data, store every element that is not implied in its stage.
+	//elements which reference a missing stage are left out, see validate.
+	pub fn normalize(&mut self){
+		let implied=self.implied_elements();
+		for stage in &mut self.stages{
+			stage.elements.clear();
+		}
+		for (&model_id,element) in &self.elements{
+			if implied.get(&model_id)==Some(element){
+				continue;
+			}
+			if let Some(stage)=self.stages.get_mut(element.stage.0 as usize){
+				stage.elements.insert(model_id,(element.behaviour.clone(),element.force));
+			}
+		}
+	}
+}
+
+#[derive(Clone,Copy,Debug,Hash,Eq,PartialEq)]
+pub enum Severity{
+	//the mode cannot be played as intended
+	Error,
+	//the mode works but is probably not what the map maker meant
+	Warning,
+}
+#[derive(Clone,Debug,Hash,Eq,PartialEq)]
+pub enum Diagnostic{
+	NoStages,
+	StartIsZone{model_id:ModelId,zone:ZoneBehaviour},
+	MissingStage{model_id:ModelId,stage_id:StageId},
+	DuplicateOrderedCheckpoint{model_id:ModelId,stage_id:StageId},
+	OrderedAndUnorderedCheckpoint{model_id:ModelId,stage_id:StageId},
+	//the stages give one model two different roles and denormalize_data keeps only one,
+	//either two stages use the model or an explicit stage element shadows a spawn or checkpoint
+	ConflictingElements{model_id:ModelId,first:StageElement,second:StageElement},
+}
+impl Diagnostic{
+	pub const fn severity(&self)->Severity{
+		match self{
+			Diagnostic::NoStages
+			|Diagnostic::StartIsZone{..}
+			|Diagnostic::MissingStage{..}
+			|Diagnostic::ConflictingElements{..}=>Severity::Error,
+			Diagnostic::DuplicateOrderedCheckpoint{..}
+			|Diagnostic::OrderedAndUnorderedCheckpoint{..}=>Severity::Warning,
+		}
+	}
+	#[inline]
+	pub const fn is_error(&self)->bool{
+		matches!(self.severity(),Severity::Error)
+	}
+}
+impl std::fmt::Display for Diagnostic{
+	fn fmt(&self,f:&mut std::fmt::Formatter<'_>)->std::fmt::Result{
+		match self{
+			Diagnostic::NoStages=>write!(f,"mode has no stages"),
+			Diagnostic::StartIsZone{model_id,zone}=>write!(f,"start model {} is also a {zone:?} zone",model_id.get()),
+			Diagnostic::MissingStage{model_id,stage_id}=>write!(f,"model {} references missing stage {}",model_id.get(),stage_id.get()),
+			Diagnostic::DuplicateOrderedCheckpoint{model_id,stage_id}=>write!(f,"model {} appears more than once in the ordered checkpoints of stage {}",model_id.get(),stage_id.get()),
+			Diagnostic::OrderedAndUnorderedCheckpoint{model_id,stage_id}=>write!(f,"model {} is both an ordered and an unordered checkpoint of stage {}",model_id.get(),stage_id.get()),
+			Diagnostic::ConflictingElements{model_id,first,second}=>write!(f,"model {} is used as {first:?} and {second:?}",model_id.get()),
+		}
+	}
+}
+
+impl Mode{
+	//check the mode for mistakes, an empty list means the mode is coherent
+	pub fn validate(&self)->Vec<Diagnostic>{
+		let mut diagnostics=Vec::new();
+		if self.stages.is_empty(){
+			diagnostics.push(Diagnostic::NoStages);
+		}
+		if let Some(zone)=self.zones.get(&self.start){
+			diagnostics.push(Diagnostic::StartIsZone{model_id:self.start,zone:zone.clone()});
+		}
+		for (&model_id,element) in &self.elements{
+			if self.stages.len()<=element.stage.0 as usize{
+				diagnostics.push(Diagnostic::MissingStage{model_id,stage_id:element.stage});
+			}
+		}
+		//replay denormalize_data and watch for overwrites
+		let mut elements:HashMap<ModelId,StageElement>=HashMap::new();
+		let mut insert=|model_id:ModelId,element:StageElement,diagnostics:&mut Vec<Diagnostic>|{
+			match elements.get(&model_id){
+				Some(first) if *first!=element=>diagnostics.push(Diagnostic::ConflictingElements{model_id,first:first.clone(),second:element}),
+				Some(_)=>(),
+				None=>{elements.insert(model_id,element);},
+			}
+		};
+		for (stage_id,stage) in self.stages(){
+			insert(stage.spawn,StageElement::new(stage_id,false,StageElementBehaviour::SpawnAt),&mut diagnostics);
+			let mut ordered=HashSet::new();
+			for &model_id in &stage.ordered_checkpoints{
+				if !ordered.insert(model_id){
+					diagnostics.push(Diagnostic::DuplicateOrderedCheckpoint{model_id,stage_id});
+					continue;
+				}
+				if stage.unordered_checkpoints.contains(&model_id){
+					diagnostics.push(Diagnostic::OrderedAndUnorderedCheckpoint{model_id,stage_id});
+				}
+				insert(model_id,StageElement::new(stage_id,false,StageElementBehaviour::Checkpoint),&mut diagnostics);
+			}
+			for &model_id in &stage.unordered_checkpoints{
+				insert(model_id,StageElement::new(stage_id,false,StageElementBehaviour::Checkpoint),&mut diagnostics);
+			}
+		}
+		//explicit elements are applied after every spawn and checkpoint
+		for (stage_id,stage) in self.stages(){
+			for (model_id,behaviour,force) in stage.elements(){
+				insert(model_id,StageElement::new(stage_id,force,behaviour.clone()),&mut diagnostics);
+			}
+		}
+		diagnostics
+	}
+}
+
+//elements added to the builder take precedence over the spawns and checkpoints of the stages,
+//validate reports when they do
+pub struct ModeBuilder{
+	mode:Mode,
+	elements:HashMap<ModelId,StageElement>,
+}
+impl ModeBuilder{
+	pub fn new(style:gameplay_style::StyleModifiers,start:ModelId)->Self{
+		Self{
+			mode:Mode::new(style,start),
+			elements:HashMap::new(),
+		}
+	}
+	pub fn stage(mut self,stage:Stage)->Self{
+		self.mode.push_stage(stage);
+		self
+	}
+	pub fn name(mut self,name:impl Into<String>)->Self{
+		self.mode.name=name.into();
+		self
+	}
+	pub fn kind(mut self,kind:ModeKind)->Self{
+		self.mode.kind=kind;
+		self
+	}
+	pub fn description(mut self,description:impl Into<String>)->Self{
+		self.mode.description=description.into();
+		self
+	}
+	pub fn allowed_styles(mut self,allowed_styles:Vec<gameplay_style::StylePreset>)->Self{
+		self.mode.allowed_styles=Some(allowed_styles);
+		self
+	}
+	pub fn zone(mut self,model_id:ModelId,zone:ZoneBehaviour)->Self{
+		self.mode.insert_zone(model_id,zone);
+		self
+	}
+	pub fn element(mut self,model_id:ModelId,element:StageElement)->Self{
+		self.elements.insert(model_id,element);
+		self
+	}
+	pub fn jump_limit(mut self,model_id:ModelId,limit:u32)->Self{
+		self.mode.insert_jump_limit(model_id,limit);
+		self
+	}
+	pub fn build(mut self)->Mode{
+		for (model_id,element) in self.elements{
+			//elements of missing stages are kept so validate can report them
+			self.mode.insert_element(model_id,element);
+		}
+		self.mode.denormalize_data();
+		self.mode
 	}
 }
 
@@ -84,7 +599,171 @@ impl Modes{
 	pub fn get_mode(&self,mode:ModeId)->Option<&Mode>{
 		self.modes.get(mode.0 as usize)
 	}
-	pub fn insert(&mut self,mode:Mode){
+	pub fn get_mode_by_name(&self,name:&str)->Option<(ModeId,&Mode)>{
+		self.iter().find(|(_,mode)|mode.name==name)
+	}
+	pub fn iter(&self)->impl Iterator<Item=(ModeId,&Mode)>{
+		self.modes.iter().enumerate().map(|(mode_id,mode)|(ModeId(mode_id as u32),mode))
+	}
+	pub fn insert(&mut self,mode:Mode)->ModeId{
+		let mode_id=ModeId(self.modes.len() as u32);
 		self.modes.push(mode);
+		mode_id
+	}
+	#[inline]
+	pub fn len(&self)->usize{
+		self.modes.len()
+	}
+	#[inline]
+	pub fn is_empty(&self)->bool{
+		self.modes.is_empty()
 	}
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_validate(){
+	let model=ModelId::new;
+	let mut stage=Stage::new(model(1));
+	stage.push_ordered_checkpoint(model(2));
+	stage.push_ordered_checkpoint(model(2));
+	stage.insert_unordered_checkpoint(model(2));
+	let mode=ModeBuilder::new(gameplay_style::StyleModifiers::default(),model(0))
+		.stage(stage)
+		.stage(Stage::new(model(2)))
+		.zone(model(0),ZoneBehaviour::Finish)
+		.element(model(3),StageElement::new(StageId::new(5),true,StageElementBehaviour::Trigger))
+		.build();
+	let diagnostics=mode.validate();
+	assert_eq!(diagnostics.len(),5);
+	assert!(diagnostics.contains(&Diagnostic::StartIsZone{model_id:model(0),zone:ZoneBehaviour::Finish}));
+	assert!(diagnostics.contains(&Diagnostic::MissingStage{model_id:model(3),stage_id:StageId::new(5)}));
+	assert!(diagnostics.contains(&Diagnostic::DuplicateOrderedCheckpoint{model_id:model(2),stage_id:StageId::FIRST}));
+	assert!(diagnostics.contains(&Diagnostic::OrderedAndUnorderedCheckpoint{model_id:model(2),stage_id:StageId::FIRST}));
+	assert!(diagnostics.iter().any(|diagnostic|matches!(diagnostic,Diagnostic::ConflictingElements{model_id,..} if *model_id==model(2))));
+	assert!(Mode::new(gameplay_style::StyleModifiers::default(),model(0)).validate()==[Diagnostic::NoStages]);
+	//explicit elements which shadow a spawn or collide across stages
+	let mut stage0=Stage::new(model(1));
+	stage0.insert_element(model(1),StageElementBehaviour::Trigger,false);
+	stage0.insert_element(model(3),StageElementBehaviour::Platform,false);
+	//the same as the implied spawn, not a conflict
+	let mut stage1=Stage::new(model(2));
+	stage1.insert_element(model(2),StageElementBehaviour::SpawnAt,false);
+	stage1.insert_element(model(3),StageElementBehaviour::Check,false);
+	let mut stage2=Stage::new(model(4));
+	stage2.insert_element(model(4),StageElementBehaviour::Teleport,true);
+	let mode=ModeBuilder::new(gameplay_style::StyleModifiers::default(),model(0))
+		.stage(stage0)
+		.stage(stage1)
+		.stage(stage2)
+		.build();
+	let mut conflicts:Vec<u32>=mode.validate().iter().map(|diagnostic|match diagnostic{
+		Diagnostic::ConflictingElements{model_id,..}=>model_id.get(),
+		other=>panic!("unexpected diagnostic {other}"),
+	}).collect();
+	conflicts.sort();
+	assert_eq!(conflicts,[1,3,4]);
+	//an element inserted on a denormalized mode survives denormalizing again and is reported
+	let mut mode=ModeBuilder::new(gameplay_style::StyleModifiers::default(),model(0))
+		.stage(Stage::new(model(1)))
+		.build();
+	let trigger=StageElement::new(StageId::FIRST,false,StageElementBehaviour::Trigger);
+	mode.insert_element(model(1),trigger.clone());
+	mode.denormalize_data();
+	assert_eq!(mode.get_element(model(1)),Some(&trigger));
+	assert!(matches!(mode.validate().as_slice(),[Diagnostic::ConflictingElements{model_id,..}] if *model_id==model(1)));
+}
+
+#[test]
+fn test_zone_behaviour_from_str(){
+	assert_eq!("Finish".parse::<ZoneBehaviour>().unwrap(),ZoneBehaviour::Finish);
+	assert_eq!("Anticheat".parse::<ZoneBehaviour>().unwrap(),ZoneBehaviour::Anticheat);
+	assert_eq!("Anitcheat".parse::<ZoneBehaviour>().unwrap(),ZoneBehaviour::Anticheat);
+	assert!("Start".parse::<ZoneBehaviour>().is_err());
+	#[allow(deprecated)]
+	let old=ZoneBehaviour::Anitcheat;
+	assert_eq!(old,ZoneBehaviour::Anticheat);
+}
+
+#[test]
+fn test_normalize_round_trip(){
+	let model=ModelId::new;
+	let mut stage=Stage::new(model(1));
+	stage.push_ordered_checkpoint(model(2));
+	stage.insert_unordered_checkpoint(model(3));
+	let mut mode=ModeBuilder::new(gameplay_style::StyleModifiers::default(),model(0))
+		.stage(stage)
+		.stage(Stage::new(model(4)))
+		.element(model(5),StageElement::new(StageId::new(1),true,StageElementBehaviour::Teleport))
+		.element(model(6),StageElement::new(StageId::FIRST,false,StageElementBehaviour::Check))
+		//overrides the checkpoint
+		.element(model(3),StageElement::new(StageId::new(1),false,StageElementBehaviour::Platform))
+		.build();
+	let elements=mode.elements.clone();
+	mode.normalize();
+	assert_eq!(mode.stages[0].elements.len(),1);
+	assert_eq!(mode.stages[1].elements.len(),2);
+	mode.elements.clear();
+	mode.denormalize_data();
+	assert_eq!(mode.elements,elements);
+}
+
+#[test]
+fn test_reverse_indices(){
+	let model=ModelId::new;
+	let mut stage=Stage::new(model(1));
+	stage.push_ordered_checkpoint(model(3));
+	stage.push_ordered_checkpoint(model(2));
+	stage.insert_unordered_checkpoint(model(5));
+	stage.insert_unordered_checkpoint(model(4));
+	let mut mode=ModeBuilder::new(gameplay_style::StyleModifiers::default(),model(0))
+		.stage(stage)
+		.stage(Stage::new(model(6)))
+		.element(model(7),StageElement::new(StageId::new(1),false,StageElementBehaviour::Trigger))
+		.build();
+	assert_eq!(mode.get_model_stage(model(7)),Some(StageId::new(1)));
+	assert_eq!(mode.get_model_stage(model(0)),None);
+	let indices=mode.indices().unwrap();
+	assert_eq!(indices.get_stage_models(StageId::FIRST),[model(1),model(2),model(3),model(4),model(5)]);
+	assert_eq!(indices.get_stage_models(StageId::new(1)),[model(6),model(7)]);
+	assert!(indices.get_stage_models(StageId::new(2)).is_empty());
+	assert_eq!(indices.get_ordered_checkpoint_index(model(2)),Some(1));
+	//edits make the indices unavailable until they are rebuilt
+	mode.insert_element(model(8),StageElement::new(StageId::FIRST,false,StageElementBehaviour::Check));
+	assert!(mode.indices().is_none());
+	mode.rebuild_indices();
+	assert_eq!(mode.indices().unwrap().get_stage_models(StageId::FIRST),[model(1),model(2),model(3),model(4),model(5),model(8)]);
+	mode.get_stage_mut(StageId::new(1)).unwrap().push_ordered_checkpoint(model(9));
+	assert!(mode.indices().is_none());
+	mode.rebuild_indices();
+	assert_eq!(mode.indices().unwrap().get_ordered_checkpoint_index(model(9)),Some(0));
+	let mut hit=HashSet::new();
+	assert_eq!(mode.get_next_checkpoint(StageId::FIRST,1,&hit),Some(model(2)));
+	assert_eq!(mode.get_next_checkpoint(StageId::FIRST,2,&hit),Some(model(4)));
+	hit.insert(model(4));
+	assert_eq!(mode.get_next_checkpoint(StageId::FIRST,2,&hit),Some(model(5)));
+	hit.insert(model(5));
+	assert_eq!(mode.get_next_checkpoint(StageId::FIRST,2,&hit),None);
+}
+
+#[test]
+fn test_named_modes(){
+	use gameplay_style::StylePreset;
+	let model=ModelId::new;
+	let mut modes=Modes::default();
+	modes.insert(ModeBuilder::new(gameplay_style::StyleModifiers::default(),model(0)).name("Main").build());
+	let bonus_id=modes.insert(ModeBuilder::new(StylePreset::SourceBhop.modifiers(),model(1))
+		.name("Bonus")
+		.kind(ModeKind::Bonus(1))
+		.description("the secret room")
+		.allowed_styles(vec![StylePreset::SourceBhop,StylePreset::SourceSurf])
+		.build()
+	);
+	let (mode_id,bonus)=modes.get_mode_by_name("Bonus").unwrap();
+	assert_eq!(mode_id,bonus_id);
+	assert_eq!(bonus.kind().to_string(),"Bonus 1");
+	assert!(bonus.is_style_allowed(StylePreset::SourceSurf));
+	assert!(!bonus.is_style_allowed(StylePreset::RobloxBhop));
+	assert!(modes.get_mode(ModeId::MAIN).unwrap().is_style_allowed(StylePreset::RobloxBhop));
+	assert!(modes.get_mode_by_name("Practice").is_none());
+	assert_eq!(modes.iter().map(|(mode_id,_)|mode_id).collect::<Vec<_>>(),[ModeId::MAIN,bonus_id]);
+}
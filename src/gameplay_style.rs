@@ -1,7 +1,9 @@
 const VALVE_SCALE:i64=16;
 
+use crate::aabb::Aabb;
 use crate::integer::{Time,Ratio64,Planar64,Planar64Vec3};
 
+#[derive(Clone)]
 pub struct StyleModifiers{
 	controls_used:u32,//controls which are allowed to pass into gameplay
 	controls_mask:u32,//controls which are masked from control state (e.g. jump in scroll style)
@@ -209,14 +211,133 @@ impl StyleModifiers{
 			camera_offset:Planar64Vec3::int(0,2,0),//4.5-2.5=2
 		}
 	}
+
+	pub fn from_preset(preset:StylePreset)->Self{
+		match preset{
+			StylePreset::Neo=>Self::neo(),
+			StylePreset::RobloxBhop=>Self::roblox_bhop(),
+			StylePreset::RobloxSurf=>Self::roblox_surf(),
+			StylePreset::RobloxRocket=>Self::roblox_rocket(),
+			StylePreset::SourceBhop=>Self::source_bhop(),
+			StylePreset::SourceSurf=>Self::source_surf(),
+		}
+	}
+
+	//zones can swap in parts of a style, see StyleModifiersOverride
+	pub fn with_override(&self,style_override:&StyleModifiersOverride)->Self{
+		let mut style=self.clone();
+		if style_override.no_strafe{
+			style.strafe=None;
+		}
+		if style_override.no_jump{
+			style.controls_mask&=!Self::CONTROL_JUMP;
+		}
+		if let Some(gravity)=style_override.gravity{
+			style.gravity=gravity;
+		}
+		if let Some(walk_speed)=style_override.walk_speed{
+			style.walk_speed=walk_speed;
+		}
+		if let Some(walk_accel)=style_override.walk_accel{
+			style.walk_accel=walk_accel;
+		}
+		if let Some(swim_speed)=style_override.swim_speed{
+			style.swim_speed=swim_speed;
+		}
+		if let Some(mass)=style_override.mass{
+			style.mass=mass;
+		}
+		style
+	}
+
+	#[inline]
+	pub fn gravity(&self)->Planar64Vec3{
+		self.gravity
+	}
+	#[inline]
+	pub fn mass(&self)->Planar64{
+		self.mass
+	}
+	#[inline]
+	pub fn static_friction(&self)->Planar64{
+		self.static_friction
+	}
+	#[inline]
+	pub fn kinetic_friction(&self)->Planar64{
+		self.kinetic_friction
+	}
+	#[inline]
+	pub fn walk_speed(&self)->Planar64{
+		self.walk_speed
+	}
+	#[inline]
+	pub fn walk_accel(&self)->Planar64{
+		self.walk_accel
+	}
+	#[inline]
+	pub fn ladder_speed(&self)->Planar64{
+		self.ladder_speed
+	}
+	#[inline]
+	pub fn ladder_dot(&self)->Planar64{
+		self.ladder_dot
+	}
+	#[inline]
+	pub fn swim_speed(&self)->Planar64{
+		self.swim_speed
+	}
+	#[inline]
+	pub fn hitbox(&self)->&Hitbox{
+		&self.hitbox
+	}
+}
+
+//the built in styles, modes refer to these to restrict which styles may be played
+#[derive(Clone,Copy,Debug,Hash,Eq,PartialEq)]
+pub enum StylePreset{
+	Neo,
+	RobloxBhop,
+	RobloxSurf,
+	RobloxRocket,
+	SourceBhop,
+	SourceSurf,
+}
+impl StylePreset{
+	pub const ALL:[Self;6]=[
+		Self::Neo,
+		Self::RobloxBhop,
+		Self::RobloxSurf,
+		Self::RobloxRocket,
+		Self::SourceBhop,
+		Self::SourceSurf,
+	];
+	#[inline]
+	pub fn modifiers(self)->StyleModifiers{
+		StyleModifiers::from_preset(self)
+	}
 }
 
+//the subset of StyleModifiers which a zone can swap in.
+//None leaves the value alone, the flags can only take abilities away.
+#[derive(Clone,Default,Hash,Eq,PartialEq)]
+pub struct StyleModifiersOverride{
+	pub no_strafe:bool,
+	pub no_jump:bool,
+	pub gravity:Option<Planar64Vec3>,
+	pub walk_speed:Option<Planar64>,
+	pub walk_accel:Option<Planar64>,
+	pub swim_speed:Option<Planar64>,
+	pub mass:Option<Planar64>,
+}
+
+#[derive(Clone)]
 enum JumpCalculation{
 	Capped,//roblox
 	Energy,//new
 	Linear,//source
 }
 
+#[derive(Clone)]
 enum JumpImpulse{
 	FromTime(Time),//jump time is invariant across mass and gravity changes
 	FromHeight(Planar64),//jump height is invariant across mass and gravity changes
@@ -228,6 +349,7 @@ enum JumpImpulse{
 //Energy means it adds energy
 //Linear means it linearly adds on
 
+#[derive(Clone)]
 enum EnableStrafe{
 	Always,
 	MaskAny(u32),//hsw, shsw
@@ -235,13 +357,15 @@ enum EnableStrafe{
 	//Function(Box<dyn Fn(u32)->bool>),
 }
 
+#[derive(Clone)]
 struct StrafeSettings{
 	enable:EnableStrafe,
 	air_accel_limit:Option<Planar64>,
 	tick_rate:Ratio64,
 }
 
-enum HitboxMesh{
+#[derive(Clone,Copy,Hash,Eq,PartialEq)]
+pub enum HitboxMesh{
 	Box,//source
 	Cylinder,//roblox
 	//Sphere,//roblox old physics
@@ -250,11 +374,43 @@ enum HitboxMesh{
 	//DualCone,
 }
 
-struct Hitbox{
+#[derive(Clone)]
+pub struct Hitbox{
 	halfsize:Planar64Vec3,
 	mesh:HitboxMesh,
 }
+//pi*2^32, the cylinder fills pi/4 of its bounding box
+const PLANAR64_PI:Planar64=Planar64::raw(13493037705);
 impl Hitbox{
+	#[inline]
+	pub fn halfsize(&self)->Planar64Vec3{
+		self.halfsize
+	}
+	#[inline]
+	pub fn mesh(&self)->HitboxMesh{
+		self.mesh
+	}
+	#[inline]
+	fn mesh_fill(&self,box_volume:Planar64)->Planar64{
+		match self.mesh{
+			HitboxMesh::Box=>box_volume,
+			//the cylinder stands along y
+			HitboxMesh::Cylinder=>box_volume*PLANAR64_PI/4,
+		}
+	}
+	pub fn volume(&self)->Planar64{
+		let size=self.halfsize*2;
+		self.mesh_fill(size.x()*size.y()*size.z())
+	}
+	//volume of the hitbox centered at position which is inside the water volume.
+	//the cylinder is treated as evenly spread through its bounding box,
+	//which is exact when it is horizontally inside the water.
+	pub fn submerged_volume(&self,position:Planar64Vec3,water:&Aabb)->Planar64{
+		let min=(position-self.halfsize).max(water.min());
+		let max=(position+self.halfsize).min(water.max());
+		let overlap=(max-min).max(Planar64Vec3::ZERO);
+		self.mesh_fill(overlap.x()*overlap.y()*overlap.z())
+	}
 	fn roblox()->Self{
 		Self{
 			halfsize:Planar64Vec3::int(2,5,2)/2,
@@ -9,7 +9,6 @@ impl Time{
 	pub const ONE_MILLISECOND:Self=Self(1_000_000);
 	pub const ONE_MICROSECOND:Self=Self(1_000);
 	pub const ONE_NANOSECOND:Self=Self(1);
-	pub const SCALE:Self=Self(19.44444);
 	#[inline]
 	pub fn from_secs(num:i64)->Self{
 		Self(Self::ONE_SECOND.0*num)
@@ -309,6 +308,14 @@ impl Angle32{
 		Self(theta.clamp(i32::MIN as i64,i32::MAX as i64) as i32)
 	}
 	#[inline]
+	pub fn wrap_from_radians(theta:f64)->Self{
+		Self::wrap_from_i64((theta/ANGLE32_TO_FLOAT64_RADIANS) as i64)
+	}
+	#[inline]
+	pub fn clamp_from_radians(theta:f64)->Self{
+		Self::clamp_from_i64((theta/ANGLE32_TO_FLOAT64_RADIANS) as i64)
+	}
+	#[inline]
 	pub fn get(&self)->i32{
 		self.0
 	}
@@ -411,8 +418,27 @@ impl TryFrom<[f32;3]> for Unit32Vec3{
 }
 */
 
+//integer square root: start with f64 sqrt and refine with newtons
+#[inline]
+pub fn isqrt_i128(radicand:i128)->i128{
+	if radicand<2{
+		return radicand.max(0);
+	}
+	let estimate=((radicand as f64).sqrt() as i128).max(1);
+	//one newton from anywhere lands on or above the floor of the root,
+	//from there the iteration decreases monotonically
+	let mut x=(estimate+radicand/estimate)/2;
+	loop{
+		let next=(x+radicand/x)/2;
+		if x<=next{
+			return x;
+		}
+		x=next;
+	}
+}
+
 ///[-1.0,1.0] = [-2^32,2^32]
-#[derive(Clone,Copy,Hash,Eq,Ord,PartialEq,PartialOrd)]
+#[derive(Clone,Copy,Default,Hash,Eq,Ord,PartialEq,PartialOrd)]
 pub struct Planar64(i64);
 impl Planar64{
 	pub const ZERO:Self=Self(0);
@@ -981,6 +1007,17 @@ impl std::ops::Mul<Planar64Vec3> for Planar64Mat3{
 		+self.z_axis*rhs.z()
 	}
 }
+impl std::ops::Mul<Planar64Mat3> for Planar64Mat3{
+	type Output=Planar64Mat3;
+	#[inline]
+	fn mul(self,rhs:Planar64Mat3)->Self::Output{
+		Planar64Mat3{
+			x_axis:self*rhs.x_axis,
+			y_axis:self*rhs.y_axis,
+			z_axis:self*rhs.z_axis,
+		}
+	}
+}
 impl std::ops::Div<i64> for Planar64Mat3{
 	type Output=Planar64Mat3;
 	#[inline]
@@ -1,9 +1,14 @@
 pub mod bvh;
 pub mod aabb;
+pub mod body;
 pub mod model;
 pub mod zeroes;
+pub mod polynomial;
+pub mod trajectory;
 pub mod integer;
 pub mod instruction;
 pub mod gameplay_modes;
 pub mod gameplay_style;
-pub mod gameplay_attributes;
\ No newline at end of file
+pub mod gameplay_attributes;
+pub mod map_naming;
+pub mod run;
\ No newline at end of file
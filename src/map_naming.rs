@@ -0,0 +1,185 @@
+//the naming conventions roblox bhop maps use to give parts behaviour
+use std::collections::HashMap;
+use crate::integer::{Planar64,Planar64Vec3};
+use crate::gameplay_modes::{StageId,StageElement,StageElementBehaviour,ZoneBehaviour};
+use crate::gameplay_attributes::{self as attr,CollisionAttributes};
+
+#[derive(Debug)]
+pub enum MapNamingError{
+	InvalidNumber{name:String},
+	MissingProperty{name:String,property:&'static str},
+	InvalidProperty{name:String,property:&'static str,value:String},
+}
+impl std::fmt::Display for MapNamingError{
+	fn fmt(&self,f:&mut std::fmt::Formatter<'_>)->std::fmt::Result{
+		match self{
+			MapNamingError::InvalidNumber{name}=>write!(f,"part {name:?} does not end in a valid number"),
+			MapNamingError::MissingProperty{name,property}=>write!(f,"part {name:?} is missing property {property:?}"),
+			MapNamingError::InvalidProperty{name,property,value}=>write!(f,"part {name:?} has invalid property {property:?}={value:?}"),
+		}
+	}
+}
+impl std::error::Error for MapNamingError{}
+
+//what a part is for, beyond its collision attributes
+#[derive(Debug)]
+pub enum PartRole{
+	Start,
+	Zone(ZoneBehaviour),
+	StageElement(StageElement),
+	//wormholes are linked by number, the converter points each WormholeIn at the WormholeOut model
+	WormholeIn(u32),
+	WormholeOut(u32),
+}
+pub struct ParsedPart{
+	pub attributes:CollisionAttributes,
+	pub role:Option<PartRole>,
+}
+
+//numbered names, e.g. ForceSpawn3
+const STAGE_ELEMENTS:[(&str,bool,StageElementBehaviour);7]=[
+	("ForceSpawn",true,StageElementBehaviour::SpawnAt),
+	("Spawn",false,StageElementBehaviour::SpawnAt),
+	("ForceTrigger",true,StageElementBehaviour::Trigger),
+	("Trigger",false,StageElementBehaviour::Trigger),
+	("ForceTeleport",true,StageElementBehaviour::Teleport),
+	("Teleport",false,StageElementBehaviour::Teleport),
+	("Platform",false,StageElementBehaviour::Platform),
+];
+
+//a name only follows a numbered convention if a digit follows the prefix,
+//so SpawnLocation is an ordinary part but Spawn3x is malformed
+fn numbered(name:&str,prefix:&str)->Option<Result<u32,MapNamingError>>{
+	let suffix=name.strip_prefix(prefix)?;
+	if !suffix.starts_with(|c:char|c.is_ascii_digit()){
+		return None;
+	}
+	Some(suffix.parse().map_err(|_|MapNamingError::InvalidNumber{name:name.to_owned()}))
+}
+
+struct Properties<'a>{
+	name:&'a str,
+	properties:&'a HashMap<String,String>,
+}
+impl Properties<'_>{
+	fn invalid(&self,property:&'static str,value:&str)->MapNamingError{
+		MapNamingError::InvalidProperty{name:self.name.to_owned(),property,value:value.to_owned()}
+	}
+	fn planar64(&self,property:&'static str,value:&str)->Result<Planar64,MapNamingError>{
+		value.trim().parse::<f64>().ok()
+		.and_then(|float|Planar64::try_from(float).ok())
+		.ok_or_else(||self.invalid(property,value))
+	}
+	fn scalar(&self,property:&'static str)->Result<Option<Planar64>,MapNamingError>{
+		self.properties.get(property).map(|value|self.planar64(property,value)).transpose()
+	}
+	//"x,y,z"
+	fn vector(&self,property:&'static str)->Result<Option<Planar64Vec3>,MapNamingError>{
+		let Some(value)=self.properties.get(property)else{
+			return Ok(None);
+		};
+		let mut components=value.split(',');
+		match (components.next(),components.next(),components.next(),components.next()){
+			(Some(x),Some(y),Some(z),None)=>Ok(Some(Planar64Vec3::new(
+				self.planar64(property,x)?,
+				self.planar64(property,y)?,
+				self.planar64(property,z)?,
+			))),
+			_=>Err(self.invalid(property,value)),
+		}
+	}
+	fn required_vector(&self,property:&'static str)->Result<Planar64Vec3,MapNamingError>{
+		self.vector(property)?.ok_or_else(||MapNamingError::MissingProperty{name:self.name.to_owned(),property})
+	}
+}
+
+fn contact(general:attr::GeneralAttributes)->CollisionAttributes{
+	CollisionAttributes::Contact{contacting:attr::ContactingAttributes::default(),general}
+}
+fn intersect(general:attr::GeneralAttributes)->CollisionAttributes{
+	CollisionAttributes::Intersect{intersecting:attr::IntersectingAttributes::default(),general}
+}
+
+//turn a part name and its key/value properties into attributes.
+//parts which follow no convention get the default attributes.
+pub fn parse_part(name:&str,properties:&HashMap<String,String>)->Result<ParsedPart,MapNamingError>{
+	let properties=Properties{name,properties};
+	let general=attr::GeneralAttributes::default();
+	let (attributes,role)=match name{
+		"MapStart"=>(intersect(general),Some(PartRole::Start)),
+		"MapFinish"=>(intersect(general),Some(PartRole::Zone(ZoneBehaviour::Finish))),
+		"MapAnticheat"=>(intersect(general),Some(PartRole::Zone(ZoneBehaviour::Anticheat))),
+		"Accelerator"=>(intersect(attr::GeneralAttributes{
+			accelerator:Some(attr::Accelerator{acceleration:properties.required_vector("Velocity")?}),
+			..general
+		}),None),
+		"SetVelocity"=>(contact(attr::GeneralAttributes{
+			trajectory:Some(attr::SetTrajectory::Velocity(properties.required_vector("Velocity")?)),
+			..general
+		}),None),
+		"Booster"=>(contact(attr::GeneralAttributes{
+			booster:Some(attr::Booster::Velocity(properties.required_vector("Velocity")?)),
+			..general
+		}),None),
+		"Kill"=>(contact(attr::GeneralAttributes{
+			respawn:Some(attr::Respawn{destination:attr::RespawnDestination::StageSpawn,reset_velocity:true}),
+			..general
+		}),None),
+		"Ladder"=>(CollisionAttributes::Contact{
+			contacting:attr::ContactingAttributes{
+				contact_behaviour:Some(attr::ContactingBehaviour::Ladder(attr::ContactingLadder{sticky:false})),
+				..Default::default()
+			},
+			general,
+		},None),
+		"Water"=>(CollisionAttributes::Intersect{
+			intersecting:attr::IntersectingAttributes{
+				water:Some(attr::IntersectingWater{
+					viscosity:properties.scalar("Viscosity")?.unwrap_or(Planar64::ZERO),
+					density:properties.scalar("Density")?.unwrap_or(Planar64::ONE),
+					velocity:properties.vector("Velocity")?.unwrap_or(Planar64Vec3::ZERO),
+				}),
+				..Default::default()
+			},
+			general,
+		},None),
+		_=>{
+			if let Some(id)=numbered(name,"WormholeIn"){
+				(intersect(general),Some(PartRole::WormholeIn(id?)))
+			}else if let Some(id)=numbered(name,"WormholeOut"){
+				(CollisionAttributes::Decoration,Some(PartRole::WormholeOut(id?)))
+			}else if let Some((stage,force,behaviour))=STAGE_ELEMENTS.iter().find_map(|(prefix,force,behaviour)|
+				numbered(name,prefix).map(|stage|(stage,force,behaviour))
+			){
+				let element=StageElement::new(StageId::new(stage?),*force,behaviour.clone());
+				//spawns and platforms are stood on, the rest are walked through
+				let attributes=match behaviour{
+					StageElementBehaviour::SpawnAt|StageElementBehaviour::Platform=>contact(general),
+					_=>intersect(general),
+				};
+				(attributes,Some(PartRole::StageElement(element)))
+			}else{
+				(CollisionAttributes::default(),None)
+			}
+		},
+	};
+	Ok(ParsedPart{attributes,role})
+}
+
+#[test]
+fn test_parse_part(){
+	let mut properties=HashMap::new();
+	let part=parse_part("ForceTeleport12",&properties).unwrap();
+	assert!(matches!(part.attributes,CollisionAttributes::Intersect{..}));
+	assert!(matches!(part.role,Some(PartRole::StageElement(element))
+		if element==StageElement::new(StageId::new(12),true,StageElementBehaviour::Teleport)));
+	assert!(parse_part("SpawnLocation",&properties).unwrap().role.is_none());
+	assert!(matches!(parse_part("Spawn3x",&properties),Err(MapNamingError::InvalidNumber{..})));
+	assert!(matches!(parse_part("Booster",&properties),Err(MapNamingError::MissingProperty{..})));
+	properties.insert("Velocity".to_owned(),"0,50,-2.5".to_owned());
+	let part=parse_part("Booster",&properties).unwrap();
+	assert!(part.attributes==contact(attr::GeneralAttributes{
+		booster:Some(attr::Booster::Velocity(Planar64Vec3::new(Planar64::ZERO,Planar64::int(50),Planar64::int(-5)/2))),
+		..Default::default()
+	}));
+}
@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use crate::integer::{Planar64Vec3,Planar64Affine3};
+use crate::gameplay_modes::ModeId;
 use crate::gameplay_attributes;
 
 pub type TextureCoordinate=glam::Vec2;
@@ -44,11 +46,28 @@ pub struct IndexedModel{
 	pub physics_sets:Vec<IndexedPhysicsGroup>,
 }
 
-#[derive(Clone,Copy,Hash,Eq,PartialEq)]
+#[derive(Clone,Copy,Debug,Hash,Eq,PartialEq)]
 pub struct ModelId(u32);
+impl ModelId{
+	#[inline]
+	pub const fn new(id:u32)->Self{
+		Self(id)
+	}
+	#[inline]
+	pub const fn get(&self)->u32{
+		self.0
+	}
+}
 pub struct Model{
 	pub model:ModelId,
-	pub attributes:gameplay_attributes::CollisionAttributesId,
+	pub attributes:gameplay_attributes::CollisionAttributesId,//used in every mode without an override
+	pub mode_attributes:HashMap<ModeId,gameplay_attributes::CollisionAttributesId>,//e.g. a booster in the main mode and decoration in a bonus
 	pub color:Color4,//transparency is in here
 	pub transform:Planar64Affine3,
 }
+impl Model{
+	#[inline]
+	pub fn attributes_for_mode(&self,mode:ModeId)->gameplay_attributes::CollisionAttributesId{
+		self.mode_attributes.get(&mode).copied().unwrap_or(self.attributes)
+	}
+}
@@ -0,0 +1,100 @@
+use crate::integer::{Time,Planar64,Planar64Vec3};
+use crate::zeroes::{self,Roots};
+
+//coefficients are in ascending order: c[0]+c[1]*t+c[2]*t^2+...
+//t is measured in seconds
+#[derive(Clone,Copy,Hash,Eq,PartialEq)]
+pub struct Polynomial<T,const N:usize>([T;N]);
+impl<T,const N:usize> Polynomial<T,N>{
+	#[inline]
+	pub const fn new(coefficients:[T;N])->Self{
+		Self(coefficients)
+	}
+	#[inline]
+	pub fn coefficients(&self)->&[T;N]{
+		&self.0
+	}
+}
+impl<T:Copy+std::ops::Div<i64,Output=T>> Polynomial<T,3>{
+	//p+v*t+a*t^2/2
+	#[inline]
+	pub fn kinematic(position:T,velocity:T,acceleration:T)->Self{
+		Self([position,velocity,acceleration/2])
+	}
+}
+impl<T:Copy+Default+std::ops::Add<T,Output=T>+std::ops::Mul<Time,Output=T>,const N:usize> Polynomial<T,N>{
+	#[inline]
+	pub fn eval(&self,time:Time)->T{
+		//horner's method, the empty polynomial is zero
+		self.0.iter().rev().fold(T::default(),|acc,&c|acc*time+c)
+	}
+}
+impl<T:Copy+std::ops::Mul<i64,Output=T>> Polynomial<T,3>{
+	#[inline]
+	pub fn derivative(&self)->Polynomial<T,2>{
+		Polynomial([self.0[1],self.0[2]*2])
+	}
+}
+impl<T:Copy> Polynomial<T,2>{
+	#[inline]
+	pub fn derivative(&self)->Polynomial<T,1>{
+		Polynomial([self.0[1]])
+	}
+}
+impl<const N:usize> Polynomial<Planar64Vec3,N>{
+	//project the vector polynomial onto a plane normal
+	#[inline]
+	pub fn dot(&self,normal:Planar64Vec3)->Polynomial<Planar64,N>{
+		Polynomial(self.0.map(|c|c.dot(normal)))
+	}
+}
+impl Polynomial<Planar64,3>{
+	#[inline]
+	pub fn zeroes(&self)->Roots<Planar64,2>{
+		zeroes::zeroes2(self.0[0],self.0[1],self.0[2])
+	}
+	#[inline]
+	pub fn zeroes_in(&self,t0:Time,t1:Time)->Roots<Time,2>{
+		zeroes::zeroes2_in(self.0[0],self.0[1],self.0[2],t0,t1)
+	}
+}
+impl Polynomial<Planar64,2>{
+	#[inline]
+	pub fn zeroes(&self)->Roots<Planar64,1>{
+		zeroes::zeroes1(self.0[0],self.0[1])
+	}
+	#[inline]
+	pub fn zeroes_in(&self,t0:Time,t1:Time)->Roots<Time,1>{
+		zeroes::zeroes1_in(self.0[0],self.0[1],t0,t1)
+	}
+}
+impl<T:Copy+std::ops::Add<T,Output=T>,const N:usize> std::ops::Add<Polynomial<T,N>> for Polynomial<T,N>{
+	type Output=Polynomial<T,N>;
+	#[inline]
+	fn add(self,rhs:Self)->Self::Output{
+		Polynomial(std::array::from_fn(|i|self.0[i]+rhs.0[i]))
+	}
+}
+impl<T:Copy+std::ops::Sub<T,Output=T>,const N:usize> std::ops::Sub<Polynomial<T,N>> for Polynomial<T,N>{
+	type Output=Polynomial<T,N>;
+	#[inline]
+	fn sub(self,rhs:Self)->Self::Output{
+		Polynomial(std::array::from_fn(|i|self.0[i]-rhs.0[i]))
+	}
+}
+
+#[test]
+fn test_polynomial(){
+	//height of a ball thrown up at 20u/s under 10u/s/s of gravity
+	let height=Polynomial::kinematic(Planar64::int(5),Planar64::int(20),Planar64::int(-10));
+	assert!(height.eval(Time::from_secs(2))==Planar64::int(25));
+	assert!(height.eval(Time::ZERO)==Planar64::int(5));
+	let speed=height.derivative();
+	assert!(speed.eval(Time::from_secs(3))==Planar64::int(-10));
+	assert!(speed.derivative().eval(Time::from_secs(7))==Planar64::int(-10));
+	assert_eq!(speed.zeroes_in(Time::ZERO,Time::from_secs(10)).as_slice(),[Time::from_secs(2)]);
+	//5+20t-5t^2=0 at t=2-sqrt(5) and t=2+sqrt(5)
+	assert_eq!(height.zeroes_in(Time::ZERO,Time::from_secs(10)).as_slice(),[Time::from_nanos(4_236_067_978)]);
+	assert_eq!(height.zeroes_in(Time::from_secs(-10),Time::from_secs(10)).as_slice(),[Time::from_nanos(-236_067_977),Time::from_nanos(4_236_067_978)]);
+	assert!(Polynomial::<Planar64,0>::new([]).eval(Time::ONE_SECOND)==Planar64::ZERO);
+}
@@ -0,0 +1,489 @@
+//run progress through a mode, driven by what the player touches
+use std::collections::{HashSet,VecDeque};
+use crate::integer::Time;
+use crate::model::ModelId;
+use crate::instruction::{InstructionConsumer,TimedInstruction};
+use crate::gameplay_modes::{Mode,StageId,StageElementBehaviour,ZoneBehaviour};
+
+#[derive(Clone,Debug)]
+pub enum RunInstruction{
+	//wrcp is whether the touched part can start a stage record, see GeneralAttributes::is_wrcp
+	Touch{model_id:ModelId,wrcp:bool},
+	Leave(ModelId),
+	Jump,
+	//restart key
+	Reset,
+}
+
+#[derive(Clone,Debug,Hash,Eq,PartialEq)]
+pub enum RunEvent{
+	//left the start zone
+	Started,
+	StageReached{stage_id:StageId,wrcp:bool},
+	CheckpointHit{stage_id:StageId,model_id:ModelId},
+	//tried to leave the stage without all of its checkpoints
+	CheckFailed{stage_id:StageId,model_id:ModelId},
+	//the caller should move the player to this spawn model
+	Teleport(ModelId),
+	Finished,
+	Invalidated(ModelId),
+	Reset,
+	//jumped with no jumps remaining
+	JumpLimit,
+}
+
+#[derive(Clone,Copy,Debug,Hash,Eq,PartialEq)]
+pub enum RunState{
+	NotStarted,
+	Running,
+	Finished,
+	//touched this anticheat zone
+	Invalidated(ModelId),
+}
+
+pub struct RunTracker<'a>{
+	mode:&'a Mode,
+	state:RunState,
+	stage:StageId,
+	//index of the next ordered checkpoint of the current stage
+	ordered_checkpoint:usize,
+	unordered_checkpoints:HashSet<ModelId>,
+	jumps_remaining:Option<u32>,
+	events:VecDeque<TimedInstruction<RunEvent>>,
+}
+impl<'a> RunTracker<'a>{
+	pub fn new(mode:&'a Mode)->Self{
+		Self{
+			mode,
+			state:RunState::NotStarted,
+			stage:StageId::FIRST,
+			ordered_checkpoint:0,
+			unordered_checkpoints:HashSet::new(),
+			jumps_remaining:None,
+			events:VecDeque::new(),
+		}
+	}
+	#[inline]
+	pub fn mode(&self)->&'a Mode{
+		self.mode
+	}
+	#[inline]
+	pub fn state(&self)->RunState{
+		self.state
+	}
+	#[inline]
+	pub fn stage(&self)->StageId{
+		self.stage
+	}
+	#[inline]
+	pub fn jumps_remaining(&self)->Option<u32>{
+		self.jumps_remaining
+	}
+	#[inline]
+	pub fn can_jump(&self)->bool{
+		self.jumps_remaining!=Some(0)
+	}
+	pub fn next_ordered_checkpoint(&self)->Option<ModelId>{
+		self.mode.get_stage(self.stage)
+		.and_then(|stage|stage.ordered_checkpoints().get(self.ordered_checkpoint).copied())
+	}
+	//for highlighting the checkpoint the player should go for
+	#[inline]
+	pub fn next_checkpoint(&self)->Option<ModelId>{
+		self.mode.get_next_checkpoint(self.stage,self.ordered_checkpoint,&self.unordered_checkpoints)
+	}
+	#[inline]
+	pub fn unordered_checkpoints(&self)->&HashSet<ModelId>{
+		&self.unordered_checkpoints
+	}
+	pub fn checkpoints_complete(&self)->bool{
+		self.mode.get_stage(self.stage).is_none_or(|stage|
+			stage.ordered_checkpoints().len()<=self.ordered_checkpoint
+			&&stage.unordered_checkpoints().iter().all(|model_id|self.unordered_checkpoints.contains(model_id))
+		)
+	}
+	//events produced since the last drain, in order
+	pub fn drain_events(&mut self)->impl Iterator<Item=TimedInstruction<RunEvent>>+'_{
+		self.events.drain(..)
+	}
+	fn push_event(&mut self,time:Time,event:RunEvent){
+		self.events.push_back(TimedInstruction{time,instruction:event});
+	}
+	fn set_stage(&mut self,time:Time,stage_id:StageId,wrcp:bool){
+		self.stage=stage_id;
+		self.ordered_checkpoint=0;
+		self.unordered_checkpoints.clear();
+		self.push_event(time,RunEvent::StageReached{stage_id,wrcp});
+	}
+	fn teleport_to_stage(&mut self,time:Time){
+		if let Some(spawn)=self.mode.get_spawn_model_id(self.stage){
+			self.push_event(time,RunEvent::Teleport(spawn));
+		}
+	}
+	fn reset(&mut self,time:Time){
+		let was_reset=self.state==RunState::NotStarted&&self.stage==StageId::FIRST&&self.ordered_checkpoint==0&&self.unordered_checkpoints.is_empty();
+		self.state=RunState::NotStarted;
+		self.stage=StageId::FIRST;
+		self.ordered_checkpoint=0;
+		self.unordered_checkpoints.clear();
+		self.jumps_remaining=None;
+		if !was_reset{
+			self.push_event(time,RunEvent::Reset);
+		}
+	}
+	fn touch(&mut self,time:Time,model_id:ModelId,wrcp:bool){
+		if model_id==self.mode.get_start_model_id(){
+			self.reset(time);
+			return;
+		}
+		if let Some(limit)=self.mode.get_jump_limit(model_id){
+			self.jumps_remaining=Some(limit);
+		}
+		match self.mode.get_zone(model_id){
+			Some(ZoneBehaviour::Finish)=>{
+				if self.state==RunState::Running{
+					if self.checkpoints_complete(){
+						self.state=RunState::Finished;
+						self.push_event(time,RunEvent::Finished);
+					}else{
+						self.push_event(time,RunEvent::CheckFailed{stage_id:self.stage,model_id});
+						self.teleport_to_stage(time);
+					}
+				}
+				return;
+			},
+			//the run is invalidated but stage elements keep working
+			Some(ZoneBehaviour::Anticheat)=>{
+				if self.state==RunState::Running{
+					self.state=RunState::Invalidated(model_id);
+					self.push_event(time,RunEvent::Invalidated(model_id));
+				}
+				return;
+			},
+			None=>(),
+		}
+		let Some(element)=self.mode.get_element(model_id)else{
+			return;
+		};
+		if *element.behaviour()==StageElementBehaviour::Checkpoint{
+			self.touch_checkpoint(time,model_id,element.stage());
+			return;
+		}
+		//every element acts like a Check when the current stage is incomplete
+		let advancing=self.stage<element.stage();
+		if (advancing||*element.behaviour()==StageElementBehaviour::Check)&&!self.checkpoints_complete(){
+			self.push_event(time,RunEvent::CheckFailed{stage_id:self.stage,model_id});
+			self.teleport_to_stage(time);
+			return;
+		}
+		//only forced elements may send the player back to a lower stage
+		if advancing||element.force()&&element.stage()!=self.stage{
+			self.set_stage(time,element.stage(),wrcp);
+		}
+		if *element.behaviour()==StageElementBehaviour::Teleport{
+			self.teleport_to_stage(time);
+		}
+	}
+	fn touch_checkpoint(&mut self,time:Time,model_id:ModelId,stage_id:StageId){
+		if stage_id!=self.stage{
+			return;
+		}
+		let Some(stage)=self.mode.get_stage(stage_id)else{
+			return;
+		};
+		//a model may be both an ordered and an unordered checkpoint
+		let mut hit=false;
+		if stage.ordered_checkpoints().get(self.ordered_checkpoint)==Some(&model_id){
+			self.ordered_checkpoint+=1;
+			hit=true;
+		}
+		if stage.unordered_checkpoints().contains(&model_id){
+			hit|=self.unordered_checkpoints.insert(model_id);
+		}
+		if hit{
+			self.push_event(time,RunEvent::CheckpointHit{stage_id,model_id});
+		}
+	}
+}
+impl InstructionConsumer<RunInstruction> for RunTracker<'_>{
+	fn process_instruction(&mut self,instruction:TimedInstruction<RunInstruction>){
+		let time=instruction.time;
+		match instruction.instruction{
+			RunInstruction::Touch{model_id,wrcp}=>self.touch(time,model_id,wrcp),
+			RunInstruction::Leave(model_id)=>{
+				if model_id==self.mode.get_start_model_id()&&self.state==RunState::NotStarted{
+					self.state=RunState::Running;
+					self.push_event(time,RunEvent::Started);
+				}
+			},
+			RunInstruction::Jump=>match self.jumps_remaining{
+				Some(0)=>self.push_event(time,RunEvent::JumpLimit),
+				Some(ref mut remaining)=>*remaining-=1,
+				None=>(),
+			},
+			RunInstruction::Reset=>{
+				self.reset(time);
+				self.teleport_to_stage(time);
+			},
+		}
+	}
+}
+
+
+//times are measured from leaving the start zone
+#[derive(Clone,Debug,Hash,Eq,PartialEq)]
+pub struct StageSplit{
+	pub stage_id:StageId,
+	//entered through a wrcp element, so this split can count for the stage record
+	pub wrcp:bool,
+	pub entered:Time,
+	pub left:Time,
+	pub checkpoints:Vec<(ModelId,Time)>,
+}
+impl StageSplit{
+	#[inline]
+	pub fn duration(&self)->Time{
+		self.left-self.entered
+	}
+}
+
+#[derive(Clone,Debug,Hash,Eq,PartialEq)]
+pub struct RunSummary{
+	pub time:Time,
+	//in the order they were played, a stage appears again if the player was sent back to it
+	pub stages:Vec<StageSplit>,
+}
+impl RunSummary{
+	//total time spent in the stage across every visit
+	pub fn stage_time(&self,stage_id:StageId)->Option<Time>{
+		self.stages.iter().filter(|split|split.stage_id==stage_id)
+		.map(StageSplit::duration)
+		.reduce(|a,b|a+b)
+	}
+	pub fn wrcp_splits(&self)->impl Iterator<Item=&StageSplit>{
+		self.stages.iter().filter(|split|split.wrcp)
+	}
+	#[inline]
+	pub fn is_faster_than(&self,other:&RunSummary)->bool{
+		self.time<other.time
+	}
+	//negative deltas are time saved against the personal best
+	pub fn compare(&self,personal_best:&RunSummary)->RunComparison{
+		let mut stages:Vec<(StageId,Time)>=Vec::new();
+		for split in &self.stages{
+			if stages.iter().any(|&(stage_id,_)|stage_id==split.stage_id){
+				continue;
+			}
+			if let (Some(time),Some(best))=(self.stage_time(split.stage_id),personal_best.stage_time(split.stage_id)){
+				stages.push((split.stage_id,time-best));
+			}
+		}
+		RunComparison{
+			total:self.time-personal_best.time,
+			stages,
+		}
+	}
+}
+
+#[derive(Clone,Debug,Hash,Eq,PartialEq)]
+pub struct RunComparison{
+	pub total:Time,
+	//stages present in both runs
+	pub stages:Vec<(StageId,Time)>,
+}
+
+#[derive(Clone,Debug)]
+enum TimerState{
+	Waiting,
+	Running{start:Time,stages:Vec<StageSplit>},
+	Finished(RunSummary),
+	//the run touched this anticheat zone and cannot be submitted
+	Invalidated(ModelId),
+}
+
+//consumes the events of a RunTracker
+pub struct RunTimer{
+	state:TimerState,
+}
+impl Default for RunTimer{
+	fn default()->Self{
+		Self::new()
+	}
+}
+impl RunTimer{
+	pub const fn new()->Self{
+		Self{
+			state:TimerState::Waiting,
+		}
+	}
+	#[inline]
+	pub fn is_running(&self)->bool{
+		matches!(self.state,TimerState::Running{..})
+	}
+	#[inline]
+	pub fn is_invalidated(&self)->bool{
+		matches!(self.state,TimerState::Invalidated(_))
+	}
+	//the anticheat zone that invalidated the run
+	pub fn invalidated_by(&self)->Option<ModelId>{
+		match self.state{
+			TimerState::Invalidated(model_id)=>Some(model_id),
+			_=>None,
+		}
+	}
+	//time since leaving the start zone
+	pub fn time(&self,time:Time)->Option<Time>{
+		match &self.state{
+			TimerState::Running{start,..}=>Some(time-*start),
+			TimerState::Finished(summary)=>Some(summary.time),
+			TimerState::Waiting|TimerState::Invalidated(_)=>None,
+		}
+	}
+	pub fn summary(&self)->Option<&RunSummary>{
+		match &self.state{
+			TimerState::Finished(summary)=>Some(summary),
+			_=>None,
+		}
+	}
+	pub fn take_summary(&mut self)->Option<RunSummary>{
+		match std::mem::replace(&mut self.state,TimerState::Waiting){
+			TimerState::Finished(summary)=>Some(summary),
+			state=>{
+				self.state=state;
+				None
+			},
+		}
+	}
+}
+impl InstructionConsumer<RunEvent> for RunTimer{
+	fn process_instruction(&mut self,instruction:TimedInstruction<RunEvent>){
+		let time=instruction.time;
+		match instruction.instruction{
+			RunEvent::Started=>self.state=TimerState::Running{
+				start:time,
+				stages:vec![StageSplit{
+					stage_id:StageId::FIRST,
+					wrcp:false,
+					entered:Time::ZERO,
+					left:Time::ZERO,
+					checkpoints:Vec::new(),
+				}],
+			},
+			RunEvent::Reset=>self.state=TimerState::Waiting,
+			RunEvent::Invalidated(model_id)=>if self.is_running(){
+				self.state=TimerState::Invalidated(model_id);
+			},
+			RunEvent::StageReached{stage_id,wrcp}=>if let TimerState::Running{start,stages}=&mut self.state{
+				let now=time-*start;
+				if let Some(split)=stages.last_mut(){
+					split.left=now;
+				}
+				stages.push(StageSplit{
+					stage_id,
+					wrcp,
+					entered:now,
+					left:now,
+					checkpoints:Vec::new(),
+				});
+			},
+			RunEvent::CheckpointHit{model_id,..}=>if let TimerState::Running{start,stages}=&mut self.state{
+				let now=time-*start;
+				if let Some(split)=stages.last_mut(){
+					split.checkpoints.push((model_id,now));
+				}
+			},
+			RunEvent::Finished=>if let TimerState::Running{start,stages}=&mut self.state{
+				let now=time-*start;
+				let mut stages=std::mem::take(stages);
+				if let Some(split)=stages.last_mut(){
+					split.left=now;
+				}
+				self.state=TimerState::Finished(RunSummary{time:now,stages});
+			},
+			RunEvent::CheckFailed{..}
+			|RunEvent::Teleport(_)
+			|RunEvent::JumpLimit=>(),
+		}
+	}
+}
+
+#[test]
+fn test_run_tracker(){
+	use crate::gameplay_modes::{ModeBuilder,Stage,StageElement};
+	let model=ModelId::new;
+	let mut stage1=Stage::new(model(10));
+	stage1.push_ordered_checkpoint(model(11));
+	stage1.push_ordered_checkpoint(model(12));
+	let mode=ModeBuilder::new(Default::default(),model(0))
+		.stage(Stage::new(model(1)))
+		.stage(stage1)
+		.zone(model(2),ZoneBehaviour::Finish)
+		.element(model(3),StageElement::new(StageId::new(1),false,StageElementBehaviour::Trigger))
+		.element(model(4),StageElement::new(StageId::FIRST,true,StageElementBehaviour::Teleport))
+		.jump_limit(model(5),1)
+		.build();
+	let mut tracker=RunTracker::new(&mode);
+	let run=|tracker:&mut RunTracker,instruction|{
+		tracker.process_instruction(TimedInstruction{time:Time::ZERO,instruction});
+		tracker.drain_events().map(|event|event.instruction).collect::<Vec<_>>()
+	};
+	assert_eq!(run(&mut tracker,RunInstruction::Leave(model(0))),[RunEvent::Started]);
+	assert_eq!(run(&mut tracker,RunInstruction::Touch{model_id:model(3),wrcp:false}),[RunEvent::StageReached{stage_id:StageId::new(1),wrcp:false}]);
+	//out of order
+	assert!(run(&mut tracker,RunInstruction::Touch{model_id:model(12),wrcp:false}).is_empty());
+	assert_eq!(run(&mut tracker,RunInstruction::Touch{model_id:model(2),wrcp:false}),[
+		RunEvent::CheckFailed{stage_id:StageId::new(1),model_id:model(2)},
+		RunEvent::Teleport(model(10)),
+	]);
+	assert_eq!(run(&mut tracker,RunInstruction::Touch{model_id:model(11),wrcp:false}),[RunEvent::CheckpointHit{stage_id:StageId::new(1),model_id:model(11)}]);
+	assert_eq!(run(&mut tracker,RunInstruction::Touch{model_id:model(12),wrcp:false}),[RunEvent::CheckpointHit{stage_id:StageId::new(1),model_id:model(12)}]);
+	//forced teleport back to the first stage
+	assert_eq!(run(&mut tracker,RunInstruction::Touch{model_id:model(4),wrcp:false}),[RunEvent::StageReached{stage_id:StageId::FIRST,wrcp:false},RunEvent::Teleport(model(1))]);
+	run(&mut tracker,RunInstruction::Touch{model_id:model(5),wrcp:false});
+	assert!(run(&mut tracker,RunInstruction::Jump).is_empty());
+	assert_eq!(run(&mut tracker,RunInstruction::Jump),[RunEvent::JumpLimit]);
+	assert_eq!(run(&mut tracker,RunInstruction::Touch{model_id:model(2),wrcp:false}),[RunEvent::Finished]);
+	assert_eq!(tracker.state(),RunState::Finished);
+	assert_eq!(run(&mut tracker,RunInstruction::Touch{model_id:model(0),wrcp:false}),[RunEvent::Reset]);
+}
+
+#[test]
+fn test_run_timer(){
+	let event=|nanos:i64,instruction|TimedInstruction{time:Time::from_nanos(nanos),instruction};
+	let run=|events:Vec<TimedInstruction<RunEvent>>|{
+		let mut timer=RunTimer::new();
+		for instruction in events{
+			timer.process_instruction(instruction);
+		}
+		timer.take_summary()
+	};
+	let summary=run(vec![
+		event(100,RunEvent::Started),
+		event(150,RunEvent::CheckpointHit{stage_id:StageId::FIRST,model_id:ModelId::new(7)}),
+		event(200,RunEvent::StageReached{stage_id:StageId::new(1),wrcp:true}),
+		event(500,RunEvent::Finished),
+	]).unwrap();
+	assert_eq!(summary.time,Time::from_nanos(400));
+	assert_eq!(summary.stages[0].checkpoints,[(ModelId::new(7),Time::from_nanos(50))]);
+	assert_eq!(summary.stage_time(StageId::new(1)),Some(Time::from_nanos(300)));
+	assert_eq!(summary.wrcp_splits().count(),1);
+	let personal_best=run(vec![
+		event(0,RunEvent::Started),
+		event(50,RunEvent::StageReached{stage_id:StageId::new(1),wrcp:false}),
+		event(450,RunEvent::Finished),
+	]).unwrap();
+	let comparison=summary.compare(&personal_best);
+	assert_eq!(comparison.total,Time::from_nanos(-50));
+	assert_eq!(comparison.stages,[(StageId::FIRST,Time::from_nanos(50)),(StageId::new(1),Time::from_nanos(-100))]);
+	assert!(summary.is_faster_than(&personal_best));
+	let mut timer=RunTimer::new();
+	for instruction in [
+		event(0,RunEvent::Started),
+		event(10,RunEvent::Invalidated(ModelId::new(3))),
+		event(20,RunEvent::Finished),
+	]{
+		timer.process_instruction(instruction);
+	}
+	assert_eq!(timer.invalidated_by(),Some(ModelId::new(3)));
+	assert!(timer.take_summary().is_none());
+}
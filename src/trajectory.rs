@@ -0,0 +1,78 @@
+//ballistic trajectory analysis for previewing jumps and boosters
+use crate::body::Body;
+use crate::integer::{Time,Planar64,Planar64Vec3};
+use crate::polynomial::Polynomial;
+use crate::gameplay_style::StyleModifiers;
+
+//heights are measured along the direction opposite gravity, relative to the launch position.
+//times are relative to the launch.
+pub struct Trajectory{
+	body:Body,
+}
+impl Trajectory{
+	#[inline]
+	pub fn new(position:Planar64Vec3,velocity:Planar64Vec3,gravity:Planar64Vec3)->Self{
+		Self{
+			body:Body::new(position,velocity,gravity,Time::ZERO),
+		}
+	}
+	#[inline]
+	pub fn from_style(position:Planar64Vec3,velocity:Planar64Vec3,style:&StyleModifiers)->Self{
+		Self::new(position,velocity,style.gravity())
+	}
+	#[inline]
+	pub fn body(&self)->&Body{
+		&self.body
+	}
+	#[inline]
+	fn gravity(&self)->Planar64Vec3{
+		self.body.acceleration
+	}
+	//height above the launch position scaled by the gravity magnitude,
+	//which keeps the coefficients exact
+	fn scaled_height_polynomial(&self)->Polynomial<Planar64,3>{
+		Polynomial::kinematic(Planar64Vec3::ZERO,self.body.velocity,self.gravity()).dot(-self.gravity())
+	}
+	pub fn apex_time(&self)->Option<Time>{
+		self.body.time_to_apex(-self.gravity())
+	}
+	pub fn apex_position(&self)->Option<Planar64Vec3>{
+		self.apex_time().map(|time|self.body.extrapolated_position(time))
+	}
+	//the highest height reached, zero if it never rises
+	pub fn apex_height(&self)->Planar64{
+		let gravity=self.gravity();
+		let gravity_length=gravity.length();
+		if gravity_length==Planar64::ZERO{
+			return Planar64::ZERO;
+		}
+		let up_speed=-self.body.velocity.dot(gravity)/gravity_length;
+		if up_speed<=Planar64::ZERO{
+			return Planar64::ZERO;
+		}
+		up_speed*up_speed/(gravity_length*2)
+	}
+	//when the trajectory comes down onto the given height, None if it never reaches it
+	pub fn time_to_height(&self,height:Planar64)->Option<Time>{
+		let gravity_length=self.gravity().length();
+		if gravity_length==Planar64::ZERO{
+			//there is no up without gravity
+			return None;
+		}
+		let scaled_height=self.scaled_height_polynomial()-Polynomial::new([height*gravity_length,Planar64::ZERO,Planar64::ZERO]);
+		//the later root of a falling parabola is always the descending crossing
+		scaled_height.zeroes_in(Time::ONE_NANOSECOND,Time::MAX).last().copied()
+	}
+	//first point where the trajectory crosses the plane dot(normal,p)==offset
+	pub fn landing_point(&self,normal:Planar64Vec3,offset:Planar64)->Option<(Time,Planar64Vec3)>{
+		self.body.time_to_plane(normal,offset,Time::ONE_NANOSECOND,Time::MAX)
+		.map(|time|(time,self.body.extrapolated_position(time)))
+	}
+	//horizontal distance covered by the time it comes back down to the given height
+	pub fn horizontal_range(&self,height:Planar64)->Option<Planar64>{
+		let time=self.time_to_height(height)?;
+		let displacement=self.body.extrapolated_position(time)-self.body.position;
+		let gravity=self.gravity();
+		Some((displacement-gravity*(displacement.dot(gravity)/gravity.dot(gravity))).length())
+	}
+}
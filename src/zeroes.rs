@@ -1,40 +1,241 @@
 //find roots of polynomials
-use crate::integer::Planar64;
+use crate::integer::{Time,Planar64,isqrt_i128};
+
+//fixed capacity root list so the physics hot loop never touches the heap
+#[derive(Clone,Copy,Debug)]
+pub struct Roots<T,const N:usize>{
+	len:usize,
+	roots:[T;N],
+}
+impl<T:Copy+Default,const N:usize> Roots<T,N>{
+	#[inline]
+	fn new()->Self{
+		Self{
+			len:0,
+			roots:[T::default();N],
+		}
+	}
+	#[inline]
+	fn push(&mut self,root:T){
+		self.roots[self.len]=root;
+		self.len+=1;
+	}
+}
+impl<T,const N:usize> Roots<T,N>{
+	#[inline]
+	pub fn len(&self)->usize{
+		self.len
+	}
+	#[inline]
+	pub fn is_empty(&self)->bool{
+		self.len==0
+	}
+	#[inline]
+	pub fn as_slice(&self)->&[T]{
+		&self.roots[..self.len]
+	}
+}
+impl<T,const N:usize> std::ops::Deref for Roots<T,N>{
+	type Target=[T];
+	#[inline]
+	fn deref(&self)->&Self::Target{
+		self.as_slice()
+	}
+}
+impl<T,const N:usize> IntoIterator for Roots<T,N>{
+	type Item=T;
+	type IntoIter=std::iter::Take<std::array::IntoIter<T,N>>;
+	#[inline]
+	fn into_iter(self)->Self::IntoIter{
+		self.roots.into_iter().take(self.len)
+	}
+}
 
 #[inline]
-pub fn zeroes2(a0:Planar64,a1:Planar64,a2:Planar64) -> Vec<Planar64>{
+pub fn zeroes2(a0:Planar64,a1:Planar64,a2:Planar64)->Roots<Planar64,2>{
 	if a2==Planar64::ZERO{
-		return zeroes1(a0, a1);
+		let mut roots=Roots::new();
+		for root in zeroes1(a0,a1){
+			roots.push(root);
+		}
+		return roots;
 	}
+	let mut roots=Roots::new();
 	let radicand=a1.get() as i128*a1.get() as i128-a2.get() as i128*a0.get() as i128*4;
 	if 0<radicand {
-		//start with f64 sqrt
+		//start with f64 sqrt and refine with integer newtons
 		//failure case: 2^63 < sqrt(2^127)
-		let planar_radicand=Planar64::raw(unsafe{(radicand as f64).sqrt().to_int_unchecked()});
-		//TODO: one or two newtons
+		let planar_radicand=Planar64::raw(isqrt_i128(radicand) as i64);
 		//sort roots ascending and avoid taking the difference of large numbers
-		match (Planar64::ZERO<a2,Planar64::ZERO<a1){
-			(true, true )=>vec![(-a1-planar_radicand)/(a2*2),(a0*2)/(-a1-planar_radicand)],
-			(true, false)=>vec![(a0*2)/(-a1+planar_radicand),(-a1+planar_radicand)/(a2*2)],
-			(false,true )=>vec![(a0*2)/(-a1-planar_radicand),(-a1-planar_radicand)/(a2*2)],
-			(false,false)=>vec![(-a1+planar_radicand)/(a2*2),(a0*2)/(-a1+planar_radicand)],
-		}
-	} else if radicand==0 {
-		return vec![a1/(a2*-2)];
-	} else {
-		return vec![];
+		let (r0,r1)=match (Planar64::ZERO<a2,Planar64::ZERO<a1){
+			(true, true )=>((-a1-planar_radicand)/(a2*2),(a0*2)/(-a1-planar_radicand)),
+			(true, false)=>((a0*2)/(-a1+planar_radicand),(-a1+planar_radicand)/(a2*2)),
+			(false,true )=>((a0*2)/(-a1-planar_radicand),(-a1-planar_radicand)/(a2*2)),
+			(false,false)=>((-a1+planar_radicand)/(a2*2),(a0*2)/(-a1+planar_radicand)),
+		};
+		roots.push(r0);
+		roots.push(r1);
+	}else if radicand==0{
+		roots.push(a1/(a2*-2));
 	}
+	roots
 }
 #[inline]
-pub fn zeroes1(a0:Planar64,a1:Planar64) -> Vec<Planar64> {
-	if a1==Planar64::ZERO{
-		return vec![];
-	}else{
+pub fn zeroes1(a0:Planar64,a1:Planar64)->Roots<Planar64,1>{
+	let mut roots=Roots::new();
+	if a1!=Planar64::ZERO{
 		let q=((-a0.get() as i128)<<32)/(a1.get() as i128);
 		if i64::MIN as i128<=q&&q<=i64::MAX as i128{
-			return vec![Planar64::raw(q as i64)];
-		}else{
-			return vec![];
+			roots.push(Planar64::raw(q as i64));
+		}
+	}
+	roots
+}
+
+//the same polynomials evaluated at an integer nanosecond,
+//scaled by 10^18 so that the sign is exact: a0*10^18+a1*10^9*t+a2*t^2
+#[inline]
+fn eval_nanos(a0:i128,a1:i128,a2:i128,t:i128)->Option<i128>{
+	a0.checked_mul(1_000_000_000_000_000_000)?
+	.checked_add(a1.checked_mul(1_000_000_000)?.checked_mul(t)?)?
+	.checked_add(a2.checked_mul(t.checked_mul(t)?)?)
+}
+#[inline]
+fn eval_nanos_derivative(a1:i128,a2:i128,t:i128)->Option<i128>{
+	a1.checked_mul(1_000_000_000)?
+	.checked_add(a2.checked_mul(2)?.checked_mul(t)?)
+}
+//newton's method on the integer polynomial, then nudge to the first nanosecond
+//on or after the crossing, so every client agrees on which side of the root a time is.
+//rising is the sign of the derivative at the root.
+//None when there is no crossing in that direction near the estimate.
+fn refine_nanos(a0:i128,a1:i128,a2:i128,estimate:Time,rising:bool)->Option<Time>{
+	//the polynomial is negative before a rising root and positive before a falling root
+	let before=|t:i128|eval_nanos(a0,a1,a2,t).map(|f|if rising{f<0}else{0<f});
+	//t is the first nanosecond past the crossing
+	let crosses=|t:i128|match (before(t-1),before(t)){
+		(Some(true),Some(false))=>Some(true),
+		(Some(_),Some(_))=>Some(false),
+		_=>None,
+	};
+	let in_range=|t:i128|i64::MIN as i128<=t&&t<=i64::MAX as i128;
+	let mut t=estimate.nanos() as i128;
+	//usually one or two newtons, more when tiny coefficients made the estimate coarse
+	for _ in 0..32{
+		let (Some(f),Some(d))=(eval_nanos(a0,a1,a2,t),eval_nanos_derivative(a1,a2,t))else{
+			//too far out to check exactly
+			return Some(estimate);
+		};
+		if d==0||f/d==0{
+			break;
+		}
+		t-=f/d;
+	}
+	//newton lands within a couple nanoseconds, anything further means the estimate was bad
+	for _ in 0..16{
+		match before(t){
+			Some(true)=>t+=1,
+			Some(false)=>break,
+			None=>return Some(estimate),
 		}
 	}
-}
\ No newline at end of file
+	for _ in 0..16{
+		match before(t-1){
+			Some(false)=>t-=1,
+			Some(true)=>break,
+			None=>return Some(estimate),
+		}
+	}
+	if in_range(t)&&crosses(t)==Some(true){
+		return Some(Time::from_nanos(t as i64));
+	}
+	match crosses(estimate.nanos() as i128){
+		Some(true)|None=>Some(estimate),
+		Some(false)=>None,
+	}
+}
+//estimates further than this outside the interval are not refined
+const ESTIMATE_SLACK:i128=1_000_000_000;
+#[inline]
+fn near_interval(estimate:Time,t0:Time,t1:Time)->bool{
+	let estimate=estimate.nanos() as i128;
+	t0.nanos() as i128-ESTIMATE_SLACK<=estimate&&estimate<t1.nanos() as i128+ESTIMATE_SLACK
+}
+///Roots of a0+a1*t+a2*t^2 within [t0,t1), as exact nanoseconds in ascending order.
+///Each root is the first nanosecond on or after the sign change.
+pub fn zeroes2_in(a0:Planar64,a1:Planar64,a2:Planar64,t0:Time,t1:Time)->Roots<Time,2>{
+	let (i0,i1,i2)=(a0.get() as i128,a1.get() as i128,a2.get() as i128);
+	let planar_roots=zeroes2(a0,a1,a2);
+	let mut roots:Roots<Time,2>=Roots::new();
+	for (i,&root) in planar_roots.iter().enumerate(){
+		let estimate=Time::from(root);
+		if !near_interval(estimate,t0,t1){
+			continue;
+		}
+		let time=match (a2==Planar64::ZERO,planar_roots.len()){
+			//linear root rises with a1
+			(true,_)=>refine_nanos(i0,i1,i2,estimate,Planar64::ZERO<a1),
+			//double root does not cross zero
+			(false,1)=>Some(estimate),
+			//the first root of an upward parabola is falling
+			(false,_)=>refine_nanos(i0,i1,i2,estimate,(Planar64::ZERO<a2)==(i==1)),
+		};
+		if let Some(time)=time{
+			if t0<=time&&time<t1&&!roots.contains(&time){
+				roots.push(time);
+			}
+		}
+	}
+	if roots.len()==2&&roots.roots[1]<roots.roots[0]{
+		roots.roots.swap(0,1);
+	}
+	roots
+}
+///Root of a0+a1*t within [t0,t1), as an exact nanosecond.
+pub fn zeroes1_in(a0:Planar64,a1:Planar64,t0:Time,t1:Time)->Roots<Time,1>{
+	let mut roots=Roots::new();
+	for root in zeroes1(a0,a1){
+		let estimate=Time::from(root);
+		if !near_interval(estimate,t0,t1){
+			continue;
+		}
+		if let Some(time)=refine_nanos(a0.get() as i128,a1.get() as i128,0,estimate,Planar64::ZERO<a1){
+			if t0<=time&&time<t1{
+				roots.push(time);
+			}
+		}
+	}
+	roots
+}
+
+#[test]
+fn test_zeroes2_in(){
+	//t^2-2 has a root at 1.414213562373...s, the first nanosecond past it is 1.414213563s
+	let roots=zeroes2_in(Planar64::int(-2),Planar64::ZERO,Planar64::ONE,Time::ZERO,Time::from_secs(10));
+	assert_eq!(roots.len(),1);
+	assert_eq!(roots[0],Time::from_nanos(1_414_213_563));
+	//-t^2+2 crosses downward at the same point, the positive root is outside the interval
+	let roots=zeroes2_in(Planar64::int(2),Planar64::ZERO,-Planar64::ONE,Time::from_secs(-10),Time::ZERO);
+	assert_eq!(roots.len(),1);
+	assert_eq!(roots[0],Time::from_nanos(-1_414_213_562));
+}
+#[test]
+fn test_zeroes2_in_bad_estimates(){
+	let roots=|a0:i64,a1:i64,a2:i64|zeroes2_in(Planar64::raw(a0),Planar64::raw(a1),Planar64::raw(a2),Time::from_secs(-1000),Time::from_secs(1000));
+	let crosses=|(a0,a1,a2):(i64,i64,i64),t:Time|{
+		let f=|t:i64|eval_nanos(a0 as i128,a1 as i128,a2 as i128,t as i128).unwrap().signum();
+		f(t.nanos()-1)*f(t.nanos())<=0
+	};
+	//tiny a2, the far root must not be pulled into the interval by newton
+	for (a0,a1,a2) in [(-1149272064,807547174912,-2),(-46972928,-68473061376,8)]{
+		let found=roots(a0,a1,a2);
+		assert_eq!(found.len(),1);
+		assert!(crosses((a0,a1,a2),found[0]));
+	}
+	assert_eq!(roots(-1149272064,807547174912,-2)[0],Time::from_nanos(1423164));
+	//near double root, both crossings are within the same nanosecond
+	assert_eq!(roots(0,4,-24048697344).as_slice(),[Time::ZERO]);
+	//ascending
+	let found=roots(-2<<32,0,1<<32);
+	assert_eq!(found.as_slice(),[Time::from_nanos(-1_414_213_562),Time::from_nanos(1_414_213_563)]);
+}